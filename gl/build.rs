@@ -0,0 +1,65 @@
+use gl_generator::{Api, Fallbacks, Generator, GlobalGenerator, Profile, Registry};
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The `gl` crate's bindings in a module of their own, shadowed by commands
+/// that call `after_call` once they have returned. `GetError` is left alone,
+/// since that is what the hook calls.
+struct HookedGenerator;
+
+impl Generator for HookedGenerator {
+    fn write<W>(&self, registry: &Registry, dest: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writeln!(dest, "mod unhooked {{")?;
+        GlobalGenerator.write(registry, dest)?;
+        writeln!(dest, "}}")?;
+        writeln!(dest, "pub use self::unhooked::*;")?;
+        // Parameter types name these, as they do inside the bindings.
+        writeln!(dest, "mod __gl_imports {{ pub use std::os::raw; }}")?;
+
+        for cmd in registry
+            .cmds
+            .iter()
+            .filter(|cmd| cmd.proto.ident != "GetError")
+        {
+            let params = cmd
+                .params
+                .iter()
+                .map(|param| format!("{}: {}", param.ident, param.ty))
+                .collect::<Vec<_>>();
+            let idents = cmd
+                .params
+                .iter()
+                .map(|param| param.ident.as_str())
+                .collect::<Vec<_>>();
+            writeln!(
+                dest,
+                "#[allow(non_snake_case)] #[inline]
+                pub unsafe fn {name}({params}) -> {ty} {{
+                    let result = unhooked::{name}({idents});
+                    after_call(\"{name}\");
+                    result
+                }}",
+                name = cmd.proto.ident,
+                params = params.join(", "),
+                ty = cmd.proto.ty,
+                idents = idents.join(", "),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let mut file = File::create(&Path::new(&out_dir).join("bindings.rs")).unwrap();
+
+    Registry::new(Api::Gl, (4, 5), Profile::Core, Fallbacks::All, [])
+        .write_bindings(HookedGenerator, &mut file)
+        .unwrap();
+}
@@ -0,0 +1,34 @@
+//! OpenGL bindings, generated like the ones of the `gl` crate, but with a
+//! hook after every command.
+//!
+//! `glGetError` only says that something went wrong since the last time it
+//! was asked. Asking after every call says which call it was, which is what
+//! the hook is for; see `debug.rs` in the tutorials.
+#![allow(clippy::all)]
+
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+/// The hook, null for none.
+static AFTER_CALL: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Have debug builds call `hook` with the name of every command but
+/// `GetError`, right after it returns. Release builds never call it.
+pub fn after_each_call(hook: Option<fn(&'static str)>) {
+    let hook = hook.map_or(ptr::null_mut(), |hook| hook as *mut ());
+    AFTER_CALL.store(hook, Ordering::Relaxed);
+}
+
+#[inline]
+fn after_call(command: &'static str) {
+    if cfg!(debug_assertions) {
+        let hook = AFTER_CALL.load(Ordering::Relaxed);
+        if !hook.is_null() {
+            // Only `after_each_call` stores here, and always a `fn(&'static str)`.
+            let hook = unsafe { std::mem::transmute::<*mut (), fn(&'static str)>(hook) };
+            hook(command);
+        }
+    }
+}
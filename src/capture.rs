@@ -0,0 +1,125 @@
+//! Reading rendered frames back from GL and writing them out as image files.
+extern crate gl;
+use self::gl::types::*;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::os::raw::c_void;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An 8-bit RGBA image, stored top row first.
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Read back the currently bound read framebuffer.
+    ///
+    /// GL hands rows over bottom-up, so they are flipped here to match the
+    /// row order image files expect.
+    pub fn read_framebuffer(width: u32, height: u32) -> Image {
+        let row_len = width as usize * 4;
+        let mut pixels = vec![0u8; row_len * height as usize];
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                width as GLsizei,
+                height as GLsizei,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut c_void,
+            );
+        }
+
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for row in pixels.chunks(row_len).rev() {
+            flipped.extend_from_slice(row);
+        }
+
+        Image {
+            width,
+            height,
+            pixels: flipped,
+        }
+    }
+
+    /// Read back what the window will show once its buffers are swapped.
+    pub fn read_window(width: u32, height: u32) -> Image {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+            gl::ReadBuffer(gl::BACK);
+        }
+        Image::read_framebuffer(width, height)
+    }
+
+    /// Write the image to `path`. The format is picked from the extension:
+    /// `.ppm` writes a binary PPM, anything else a PNG.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ppm") => self.write_ppm(file),
+            _ => self.write_png(file),
+        }
+    }
+
+    fn write_png<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    /// PPM has no alpha channel, so it is dropped.
+    fn write_ppm<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in self.pixels.chunks(4) {
+            w.write_all(&pixel[0..3])?;
+        }
+        w.flush()
+    }
+}
+
+/// The current UTC time as `YYYYMMDD-HHMMSS-mmm`, for file names that sort
+/// in the order they were taken.
+pub fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = now.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let seconds = seconds % 86400;
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        now.subsec_millis()
+    )
+}
+
+/// The date `days` after 1970-01-01, after Howard Hinnant's
+/// `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // months counted from March, so the leap day comes last
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month as u32, day as u32)
+}
@@ -1,139 +1,64 @@
 #![allow(non_upper_case_globals)]
-extern crate glfw;
-use self::glfw::{Context, Key, Action};
-
-use crate::shader::Shader;
+use crate::framework::{InitError, Tutorial};
+use crate::objects::{ArrayBuffer, VertexArray};
+use crate::shader::{Shader, ShaderBuilder};
 
 extern crate gl;
-use self::gl::types::*;
-
-use std::sync::mpsc::Receiver;
-use std::ffi::CString;
-use std::ptr;
-use std::str;
-use std::mem;
-use std::os::raw::c_void;
-
-// settings
-const SCR_WIDTH: u32 = 800;
-const SCR_HEIGHT: u32 = 600;
-
-
-#[allow(non_snake_case)]
-pub fn main_hello_color() {
-    // glfw: initialize and configure
-    // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
-    #[cfg(target_os = "macos")]
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
 
-    // glfw window creation
-    // --------------------
-    let (mut window, events) = glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", glfw::WindowMode::Windowed)
-        .expect("Failed to create GLFW window");
-
-    window.make_current();
-    window.set_key_polling(true);
-    window.set_framebuffer_size_polling(true);
-
-    // gl: load all OpenGL function pointers
-    // ---------------------------------------
-    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
-
-    let shader = Shader::new("./src/ch_2/shaders/identity.vs", "./src/ch_2/shaders/gradient.fs");
+pub struct HelloColor {
+    shader: Shader,
+    vao: VertexArray,
+    #[allow(dead_code)]
+    vbo: ArrayBuffer,
+}
 
-    let (shaderProgram, VAO) = unsafe {
-        // link shaders
-        let shaderProgram = shader.ID;
+impl Tutorial for HelloColor {
+    fn init() -> Result<Self, InitError> {
+        let shader = ShaderBuilder::new()
+            .vertex("./src/ch_2/shaders/identity.vs")
+            .fragment("./src/ch_2/shaders/gradient.fs")
+            .bind_attribute(0, "aPos")
+            .bind_frag_data(0, "outputColor")
+            .build()?;
 
         // set up vertex data (and buffer(s)) and configure vertex attributes
         // ------------------------------------------------------------------
         // HINT: type annotation is crucial since default for float literals is f64
+        #[rustfmt::skip]
         let vertices: [f32; 12] = [
             0.75, 0.75, 0.5, 1.,
              0.75, -0.75, 0.0, 1.,
              -0.75,  -0.75, 0.0, 1.0
         ];
+        let vbo = ArrayBuffer::with_data(&vertices, gl::STATIC_DRAW);
 
-        let (mut VBO, mut VAO) = (0, 0);
-        gl::GenVertexArrays(1, &mut VAO);
-        // bind the Vertex Array Object first, then bind and set vertex buffer(s), and then configure vertex attributes(s).
-        gl::BindVertexArray(VAO);
-
-        // Initialize Buffer Object
-        gl::GenBuffers(1, &mut VBO);
-        gl::BindBuffer(gl::ARRAY_BUFFER, VBO);
-        gl::BufferData(gl::ARRAY_BUFFER,
-                       (vertices.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-                       &vertices[0] as *const f32 as *const c_void,
-                       gl::STATIC_DRAW);
-        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
         // We've filled the GPU memory buffer with our vertex data, but opengl
         // doesn't know that's what is inside. Next step is to tell it.
-
-        // Now we tell oepnGL what the format of the data is.
-        gl::BindBuffer(gl::ARRAY_BUFFER, VBO);
-        gl::EnableVertexAttribArray(0);
-        //                      vertex attribute index,  How many of these values represent a single
-        //                      piece of data, What the data type is, ?, spacing
-        //                      between data, the byte offset from the start
-        gl::VertexAttribPointer(0, 4, gl::FLOAT, gl::FALSE, 0, ptr::null());
-        // Previous version, Not sure why it species size of data.
-        // gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, 3 * mem::size_of::<GLfloat>() as GLsizei, ptr::null());
-
-        // note that this is allowed, the call to gl::VertexAttribPointer registered VBO as the vertex attribute's bound vertex buffer object so afterwards we can safely unbind
-        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-
-        // You can unbind the VAO afterwards so other VAO calls won't accidentally modify this VAO, but this rarely happens. Modifying other
-        // VAOs requires a call to glBindVertexArray anyways so we generally don't unbind VAOs (nor VBOs) when it's not directly necessary.
-        gl::BindVertexArray(0);
+        let vao = VertexArray::new();
+        {
+            let vao = vao.bind();
+            vao.attribute(0, &vbo, 4, 0, 0);
+        }
 
         // uncomment this call to draw in wireframe polygons.
-        // gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
-
-        (shaderProgram, VAO)
-    };
+        // unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE) };
 
-    // render loop
-    // -----------
-    while !window.should_close() {
-        // events
-        // -----
-        process_events(&mut window, &events);
+        Ok(HelloColor { shader, vao, vbo })
+    }
 
-        // render
-        // ------
+    fn display(&mut self, _time: f64) {
         unsafe {
             gl::ClearColor(0.2, 0.3, 0.3, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
             // // draw our first triangle
-            gl::UseProgram(shaderProgram);
-            gl::BindVertexArray(VAO); // seeing as we only have a single VAO there's no need to bind it every time, but we'll do so to keep things a bit more organized
+            let _program = self.shader.bind();
+            let _vao = self.vao.bind();
             gl::DrawArrays(gl::TRIANGLES, 0, 6);
-            gl::BindVertexArray(0);
         }
-
-        // glfw: swap buffers and poll IO events (keys pressed/released, mouse moved etc.)
-        // -------------------------------------------------------------------------------
-        window.swap_buffers();
-        glfw.poll_events();
     }
-}
 
-// NOTE: not the same version as in common.rs!
-fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
-    for (_, event) in glfw::flush_messages(events) {
-        match event {
-            glfw::WindowEvent::FramebufferSize(width, height) => {
-                // make sure the viewport matches the new window dimensions; note that width and
-                // height will be significantly larger than specified on retina displays.
-                unsafe { gl::Viewport(0, 0, width, height) }
-            }
-            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => window.set_should_close(true),
-            _ => {}
-        }
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.shader]
     }
 }
@@ -1,23 +1,12 @@
 #![allow(non_upper_case_globals)]
-extern crate glfw;
-use self::glfw::{Action, Context, Key};
-
+use crate::framework::{InitError, Tutorial};
+use crate::objects::{ArrayBuffer, VertexArray};
 use crate::shader::Shader;
+use crate::vertex::{ComponentType, VertexLayout};
 
 extern crate gl;
-use self::gl::types::*;
 
 use cgmath::Matrix;
-use std::ffi::CString;
-use std::mem;
-use std::os::raw::c_void;
-use std::ptr;
-use std::str;
-use std::sync::mpsc::Receiver;
-
-// settings
-const SCR_WIDTH: u32 = 800;
-const SCR_HEIGHT: u32 = 600;
 
 // set up vertex data (and buffer(s)) and configure vertex attributes
 // ------------------------------------------------------------------
@@ -72,9 +61,6 @@ const vertices: [f32; 288] = [
    -0.25f32, -0.25f32, -2.75f32, 1.0f32,
    -0.25f32, -0.25f32, -1.25f32, 1.0f32,
 
-
-
-
    0.0f32, 0.0f32, 1.0f32, 1.0f32,
    0.0f32, 0.0f32, 1.0f32, 1.0f32,
    0.0f32, 0.0f32, 1.0f32, 1.0f32,
@@ -124,24 +110,6 @@ const vertices: [f32; 288] = [
    0.0f32, 1.0f32, 1.0f32, 1.0f32,
 ];
 
-#[allow(non_snake_case)]
-unsafe fn initialize_vertex_buffer() -> u32 {
-    let mut VBO = 0;
-    // Initialize Buffer Object
-    gl::GenBuffers(1, &mut VBO);
-    gl::BindBuffer(gl::ARRAY_BUFFER, VBO);
-    gl::BufferData(
-        gl::ARRAY_BUFFER,
-        (vertices.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-        &vertices[0] as *const f32 as *const c_void,
-        gl::STREAM_DRAW,
-    );
-    gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-    // We've filled the GPU memory buffer with our vertex data, but opengl
-    // doesn't know that's what is inside. Next step is to tell it.
-    VBO
-}
-
 fn reshape(
     width: i32,
     height: i32,
@@ -151,174 +119,114 @@ fn reshape(
 ) {
     perspective_matrix.x[0] = frustum_scale / (width as f32 / height as f32);
     perspective_matrix.y[1] = frustum_scale;
+    let _program = program.bind();
     unsafe {
-        gl::UseProgram(program.ID);
-        program.setMat4(
-            &CString::new("perspectiveMatrix").unwrap(),
-            &perspective_matrix,
-        );
-        gl::UseProgram(0);
+        program.set_uniform("perspectiveMatrix", *perspective_matrix);
     }
 }
 
-#[allow(non_snake_case)]
-pub fn main() {
-    // glfw: initialize and configure
-    // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-        glfw::OpenGlProfileHint::Core,
-    ));
-    #[cfg(target_os = "macos")]
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-
-    // glfw window creation
-    // --------------------
-    let (mut window, events) = glfw
-        .create_window(
-            SCR_WIDTH,
-            SCR_HEIGHT,
-            "LearnOpenGL",
-            glfw::WindowMode::Windowed,
-        )
-        .expect("Failed to create GLFW window");
-
-    window.make_current();
-    window.set_key_polling(true);
-    window.set_framebuffer_size_polling(true);
-
-    // gl: load all OpenGL function pointers
-    // ---------------------------------------
-    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
-
-    let shader = Shader::new(
-        "./src/ch_4/shaders/matrix_perspective.vs",
-        "./src/ch_4/shaders/standard_color.fs",
-    );
-
-    let (shaderProgram, VAO, VBO) = unsafe {
-        // link shaders
-        let shaderProgram = shader.ID;
-
-        let mut VAO = 0;
-        gl::GenVertexArrays(1, &mut VAO);
-        // bind the Vertex Array Object first, then bind and set vertex buffer(s), and then configure vertex attributes(s).
-        gl::BindVertexArray(VAO);
-        gl::Enable(gl::CULL_FACE);
-        gl::CullFace(gl::BACK);
-        // gl::FrontFace(gl::CW);
-        gl::FrontFace(gl::CW);
-
-        let VBO = initialize_vertex_buffer();
-
-        // You can unbind the VAO afterwards so other VAO calls won't accidentally modify this VAO, but this rarely happens. Modifying other
-        // VAOs requires a call to glBindVertexArray anyways so we generally don't unbind VAOs (nor VBOs) when it's not directly necessary.
-        gl::BindVertexArray(0);
-
-        // uncomment this call to draw in wireframe polygons.
-        // gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
-
-        (shaderProgram, VAO, VBO)
-    };
-
-    let frustum_scale = 1.0f32;
-    let z_near = 0.5f32;
-    let z_far = 3.0f32;
-
-    // Setup Perspective Matrix
-    #[rustfmt::skip]
-    let mut matrix = cgmath::Matrix4::new(
-        frustum_scale, 0.0,           0.0,                               0.0,
-        0.0,           frustum_scale, 0.0,                               0.0,
-        0.0,           0.0,           (z_far + z_near)/(z_near - z_far), 2.0 * z_far * z_near / (z_near - z_far),
-        0.0,           0.0,                                                    -1.0, 0.0
-    ).transpose(); // Transpose because I wrote it in row major order, but it should be column major order
+pub struct AspectRatio {
+    shader: Shader,
+    vao: VertexArray,
+    #[allow(dead_code)]
+    vbo: ArrayBuffer,
+    frustum_scale: f32,
+    perspective_matrix: cgmath::Matrix4<f32>,
+}
 
-    unsafe {
-        gl::UseProgram(shaderProgram);
-        shader.setFloat(&CString::new("frustumScale").unwrap(), 1.0);
-        shader.setFloat(&CString::new("zNear").unwrap(), 1.0);
-        shader.setFloat(&CString::new("zFar").unwrap(), 3.0);
+impl Tutorial for AspectRatio {
+    #[allow(non_snake_case)]
+    fn init() -> Result<Self, InitError> {
+        let shader = Shader::new(
+            "./src/ch_4/shaders/perspective.vs",
+            "./src/ch_4/shaders/standard_color.fs",
+        )?;
+
+        let vbo = ArrayBuffer::with_data(&vertices, gl::STREAM_DRAW);
+
+        // We've filled the GPU memory buffer with our vertex data, but opengl
+        // doesn't know that's what is inside. Next step is to tell it.
+        let vao = VertexArray::new();
+        {
+            let vao = vao.bind();
+            VertexLayout::planar()
+                .attribute(0, 4, ComponentType::F32)
+                .attribute(1, 4, ComponentType::F32)
+                .apply(&vao, &vbo, 36, 0);
+        }
 
-        shader.setMat4(&CString::new("perspectiveMatrix").unwrap(), &matrix);
-        gl::UseProgram(0);
-    }
+        unsafe {
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::BACK);
+            // gl::FrontFace(gl::CW);
+            gl::FrontFace(gl::CW);
+        }
+
+        let frustum_scale = 1.0f32;
+        let z_near = 0.5f32;
+        let z_far = 3.0f32;
+
+        // Setup Perspective Matrix
+        #[rustfmt::skip]
+        let matrix = cgmath::Matrix4::new(
+            frustum_scale, 0.0,           0.0,                               0.0,
+            0.0,           frustum_scale, 0.0,                               0.0,
+            0.0,           0.0,           (z_far + z_near)/(z_near - z_far), 2.0 * z_far * z_near / (z_near - z_far),
+            0.0,           0.0,                                                    -1.0, 0.0
+        ).transpose(); // Transpose because I wrote it in row major order, but it should be column major order
+
+        unsafe {
+            let _program = shader.bind();
 
-    // render loop
-    // -----------
-    while !window.should_close() {
-        // events
-        // -----
-        process_events(&mut window, &events, &shader, frustum_scale, &mut matrix);
+            shader.set_uniform("perspectiveMatrix", matrix);
+        }
 
-        // render
-        // ------
+        Ok(AspectRatio {
+            shader,
+            vao,
+            vbo,
+            frustum_scale,
+            perspective_matrix: matrix,
+        })
+    }
+
+    fn display(&mut self, _time: f64) {
         unsafe {
             gl::ClearColor(0.2, 0.3, 0.3, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
             // // draw our first triangle
-            gl::UseProgram(shaderProgram);
-
-            shader.set2F(&CString::new("offset").unwrap(), (0.5, 0.5));
+            let _program = self.shader.bind();
 
-            gl::BindVertexArray(VAO); // seeing as we only have a single VAO there's no need to bind it every time, but we'll do so to keep things a bit more organized
+            self.shader.set_uniform("offset", (0.5, 0.5));
 
-            let color_data = (vertices.len() * mem::size_of::<GLfloat>()) / 2;
-
-            // Now we tell oepnGL what the format of the data is.
-            gl::BindBuffer(gl::ARRAY_BUFFER, VBO);
-            gl::EnableVertexAttribArray(0);
-            gl::EnableVertexAttribArray(1);
-            //                      vertex attribute index,  How many of these values represent a single
-            //                      piece of data, What the data type is, ?, spacing
-            //                      between data, the byte offset from the start
-            gl::VertexAttribPointer(0, 4, gl::FLOAT, gl::FALSE, 0, ptr::null());
-            gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, 0, color_data as *const c_void);
+            let _vao = self.vao.bind();
 
             gl::DrawArrays(gl::TRIANGLES, 0, 36);
+        }
+    }
 
-            gl::DisableVertexAttribArray(0);
-            gl::DisableVertexAttribArray(1);
-            gl::UseProgram(0);
-
-            // note that this is allowed, the call to gl::VertexAttribPointer registered VBO as the vertex attribute's bound vertex buffer object so afterwards we can safely unbind
-            // gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-            // gl::BindVertexArray(0);
+    fn reshape(&mut self, width: i32, height: i32) {
+        unsafe {
+            gl::Viewport(0, 0, width, height);
         }
+        reshape(
+            width,
+            height,
+            self.frustum_scale,
+            &mut self.perspective_matrix,
+            &self.shader,
+        )
+    }
 
-        // glfw: swap buffers and poll IO events (keys pressed/released, mouse moved etc.)
-        // -------------------------------------------------------------------------------
-        window.swap_buffers();
-        glfw.poll_events();
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.shader]
     }
-}
 
-// NOTE: not the same version as in common.rs!
-fn process_events(
-    window: &mut glfw::Window,
-    events: &Receiver<(f64, glfw::WindowEvent)>,
-    program: &Shader,
-    frustum_scale: f32,
-    perspective_matrix: &mut cgmath::Matrix4<f32>,
-) {
-    for (_, event) in glfw::flush_messages(events) {
-        match event {
-            glfw::WindowEvent::FramebufferSize(width, height) => {
-                // make sure the viewport matches the new window dimensions; note that width and
-                // height will be significantly larger than specified on retina displays.
-                unsafe {
-                    gl::Viewport(0, 0, width, height);
-                    reshape(width, height, frustum_scale, perspective_matrix, program)
-                }
-                // unsafe { gl::Viewport(width / 2, height / 2, width, height) }
-                // unsafe { gl::Viewport(0, 0, width, height / 2) }
-            }
-            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                window.set_should_close(true)
-            }
-            _ => {}
+    fn teardown(&mut self) {
+        unsafe {
+            gl::Disable(gl::CULL_FACE);
+            gl::FrontFace(gl::CCW);
         }
     }
 }
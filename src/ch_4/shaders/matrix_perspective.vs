@@ -1,17 +0,0 @@
-#version 330
-
-layout(location = 0) in vec4 position;
-layout(location = 1) in vec4 color;
-
-smooth out vec4 theColor;
-
-uniform vec2 offset;
-uniform mat4 perspectiveMatrix;
-
-void main()
-{
-    vec4 cameraPos = position + vec4(offset.x, offset.y, 0.0, 0.0);
-
-    gl_Position = perspectiveMatrix * cameraPos;
-    theColor = color;
-}
\ No newline at end of file
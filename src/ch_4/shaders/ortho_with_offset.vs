@@ -1,9 +1,6 @@
 #version 330
 
-layout(location = 0) in vec4 position;
-layout(location = 1) in vec4 color;
-
-smooth out vec4 theColor;
+#include "../../shaders/vertex_color.glsl"
 
 uniform vec2 offset;
 
@@ -11,4 +8,4 @@ void main()
 {
 	gl_Position = position + vec4(offset.x, offset.y, 0.0, 0.0);
 	theColor = color;
-}
\ No newline at end of file
+}
@@ -0,0 +1,14 @@
+#version 330
+
+#include "../../shaders/vertex_color.glsl"
+#include "../../shaders/perspective.glsl"
+
+uniform vec2 offset;
+
+void main()
+{
+	vec4 cameraPos = position + vec4(offset.x, offset.y, 0.0, 0.0);
+
+	gl_Position = perspective(cameraPos);
+	theColor = color;
+}
@@ -0,0 +1,373 @@
+//! Command line parsing. Kept by hand rather than pulling in an argument
+//! parsing crate; there are only a handful of flags.
+use crate::debug::{self, DebugOutput};
+use crate::framework::{Options, Recording};
+use crate::headless::Capture;
+use crate::shapes;
+
+use std::path::PathBuf;
+use std::slice::Iter;
+
+pub const USAGE: &str = "\
+Usage: gltut <command> [options]
+
+Commands:
+    list                 Show every chapter with a short description
+    run <chapter>        Open a window and run a chapter
+    <chapter>            Same as `run <chapter>`
+    view <file>          Show a mesh file (.obj, .gltf, .glb or .xml), turning in
+                         front of the camera; takes the same options as `run`
+    view --shape <name>  Show a generated shape instead: cube, sphere, icosphere,
+                         cylinder, cone, plane or torus
+    demo [<chapter>]     Run every chapter in one window, starting at <chapter>;
+                         PageUp/PageDown or a chapter number switch chapters
+    help                 Show this message
+
+Window options:
+    --width <pixels>     Window width (default 800)
+    --height <pixels>    Window height (default 600)
+    --fullscreen         Use the whole primary monitor
+    --no-vsync           Don't wait for vertical sync when swapping buffers
+    --msaa <samples>     Multisample anti-aliasing, e.g. 4 (default off)
+    --fixed-step <secs>  Advance time by this much per frame instead of real time
+    --freeze <secs>      Hold time still at <secs>; , and . step a frame either way
+    --screenshot         Save the first frame to screenshots/ (F12 does the same)
+    --record <dir>       Save the first --frames frames to <dir> as a PNG sequence
+
+Headless options:
+    --headless           Render offscreen and write frames out as images
+    --frames <n>         Number of frames to write or record (default 1)
+    --time <seconds>     Time of the first frame (default 0)
+    --out <dir>          Directory for the frames (default ./frames)
+    --format <png|ppm>   Image format (default png)
+
+Debug options:
+    --gl-debug           Ask for a debug context and report GL's debug messages
+    --gl-debug-sources <list>
+                         Only report these sources: api, window-system,
+                         shader-compiler, third-party, application, other
+    --gl-debug-types <list>
+                         Only report these types: error, deprecated, undefined,
+                         portability, performance, marker, push-group,
+                         pop-group, other
+    --gl-debug-severity <level>
+                         Least severe messages to report: notification, low,
+                         medium or high (default low)
+    --gl-debug-panic     Panic with a backtrace on the first high-severity error
+    The --gl-debug-* options imply --gl-debug. Without KHR_debug, debug builds
+    check glGetError after every GL call instead.
+
+Other options:
+    --time-scale <x>     Speed of tutorial time, e.g. 0.5 for half speed (default 1)
+    --dump-shader-info   Print the attributes and uniforms of every program
+    -h, --help           Show this message
+";
+
+pub enum Command {
+    Help,
+    List,
+    Run {
+        chapter: String,
+        options: Options,
+        /// Set when running headless.
+        capture: Option<Capture>,
+    },
+    /// Show a mesh file or a generated shape.
+    View {
+        model: Model,
+        options: Options,
+        /// Set when running headless.
+        capture: Option<Capture>,
+    },
+    /// Switch between all chapters in one window.
+    Demo {
+        /// Where to start, the first chapter if not given.
+        chapter: Option<String>,
+        options: Options,
+    },
+}
+
+/// What `view` shows.
+pub enum Model {
+    File(PathBuf),
+    Shape(shapes::Generator),
+}
+
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut headless = false;
+    let mut capture = Capture::default();
+    let mut record = None;
+    let mut shape = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--width" => options.width = number(arg, &mut args)?,
+            "--height" => options.height = number(arg, &mut args)?,
+            "--fullscreen" => options.fullscreen = true,
+            "--no-vsync" => options.vsync = false,
+            "--msaa" => options.samples = number(arg, &mut args)?,
+            "--fixed-step" => options.fixed_step = Some(number(arg, &mut args)?),
+            "--freeze" => options.freeze = Some(number(arg, &mut args)?),
+            "--time-scale" => options.time_scale = number(arg, &mut args)?,
+            "--dump-shader-info" => options.dump_shader_info = true,
+            "--screenshot" => options.screenshot = true,
+            "--gl-debug" => {
+                options.debug.get_or_insert_with(DebugOutput::default);
+            }
+            "--gl-debug-sources" => {
+                let sources = choices(arg, &mut args, debug::SOURCES)?;
+                options
+                    .debug
+                    .get_or_insert_with(DebugOutput::default)
+                    .sources = sources;
+            }
+            "--gl-debug-types" => {
+                let types = choices(arg, &mut args, debug::TYPES)?;
+                options.debug.get_or_insert_with(DebugOutput::default).types = types;
+            }
+            "--gl-debug-severity" => {
+                let severity = choice(arg, value(arg, &mut args)?, debug::SEVERITIES)?;
+                options
+                    .debug
+                    .get_or_insert_with(DebugOutput::default)
+                    .min_severity = severity;
+            }
+            "--gl-debug-panic" => {
+                options
+                    .debug
+                    .get_or_insert_with(DebugOutput::default)
+                    .panic_on_error = true;
+            }
+            "--shape" => shape = Some(choice(arg, value(arg, &mut args)?, shapes::SHAPES)?),
+            "--record" => record = Some(PathBuf::from(value(arg, &mut args)?)),
+            "--headless" => headless = true,
+            "--frames" => capture.frames = number(arg, &mut args)?,
+            "--time" => capture.start_time = number(arg, &mut args)?,
+            "--out" => capture.out_dir = PathBuf::from(value(arg, &mut args)?),
+            "--format" => {
+                capture.format = value(arg, &mut args)?.to_string();
+                if capture.format != "png" && capture.format != "ppm" {
+                    return Err(format!(
+                        "--format expects png or ppm, got {}",
+                        capture.format
+                    ));
+                }
+            }
+            flag if flag.starts_with('-') => return Err(format!("Unknown option {}", flag)),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    if options.width == 0 || options.height == 0 {
+        return Err("--width and --height must be greater than 0".to_string());
+    }
+    if headless && (record.is_some() || options.screenshot) {
+        return Err("--headless already saves every frame to --out".to_string());
+    }
+    if headless && options.freeze.is_some() {
+        return Err("--headless takes the time of its first frame from --time".to_string());
+    }
+    options.record = record.map(|dir| Recording {
+        dir,
+        frames: capture.frames,
+    });
+
+    let chapter = match positional.as_slice() {
+        [] | ["help"] => return Ok(Command::Help),
+        ["list"] => return Ok(Command::List),
+        ["demo", start @ ..] if start.len() <= 1 => {
+            if headless {
+                return Err("demo can't run headless".to_string());
+            }
+            return Ok(Command::Demo {
+                chapter: start.first().map(|chapter| chapter.to_string()),
+                options,
+            });
+        }
+        ["view", rest @ ..] => {
+            let model = match (rest, shape) {
+                ([], Some(shape)) => Model::Shape(shape),
+                ([path], None) => Model::File(PathBuf::from(path)),
+                ([_], Some(_)) => {
+                    return Err("view takes a mesh file or --shape, not both".to_string())
+                }
+                _ => return Err("view expects a mesh file or --shape".to_string()),
+            };
+            return Ok(Command::View {
+                model,
+                options,
+                capture: if headless { Some(capture) } else { None },
+            });
+        }
+        _ if shape.is_some() => return Err("--shape only goes with view".to_string()),
+        ["run"] => return Err("run expects a chapter, see `gltut list`".to_string()),
+        ["run", chapter] | [chapter] => chapter.to_string(),
+        _ => return Err(format!("Unexpected arguments: {}", positional.join(" "))),
+    };
+    Ok(Command::Run {
+        chapter,
+        options,
+        capture: if headless { Some(capture) } else { None },
+    })
+}
+
+fn value<'a>(flag: &str, args: &mut Iter<'a, String>) -> Result<&'a str, String> {
+    args.next()
+        .map(|value| value.as_str())
+        .ok_or_else(|| format!("{} expects a value", flag))
+}
+
+fn number<T: std::str::FromStr>(flag: &str, args: &mut Iter<String>) -> Result<T, String> {
+    let value = value(flag, args)?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got {}", flag, value))
+}
+
+/// A comma separated list of names from `table`.
+fn choices<T: Copy>(
+    flag: &str,
+    args: &mut Iter<String>,
+    table: &[(&str, T)],
+) -> Result<Vec<T>, String> {
+    value(flag, args)?
+        .split(',')
+        .map(|name| choice(flag, name.trim(), table))
+        .collect()
+}
+
+fn choice<T: Copy>(flag: &str, name: &str, table: &[(&str, T)]) -> Result<T, String> {
+    table
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, value)| *value)
+        .ok_or_else(|| {
+            let known: Vec<&str> = table.iter().map(|(known, _)| *known).collect();
+            format!("{} expects one of {}, got {}", flag, known.join(", "), name)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Command, String> {
+        let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+        parse(&args)
+    }
+
+    fn error(args: &str) -> String {
+        match parse_str(args) {
+            Err(message) => message,
+            Ok(_) => panic!("expected `{}` to be rejected", args),
+        }
+    }
+
+    fn run(args: &str) -> (String, Options, Option<Capture>) {
+        match parse_str(args) {
+            Ok(Command::Run {
+                chapter,
+                options,
+                capture,
+            }) => (chapter, options, capture),
+            Ok(_) => panic!("expected `{}` to run a chapter", args),
+            Err(message) => panic!("`{}` was rejected: {}", args, message),
+        }
+    }
+
+    #[test]
+    fn bare_chapter_is_run() {
+        let (chapter, options, capture) = run("ch_4_4 --width 640 --height 320");
+        assert_eq!(chapter, "ch_4_4");
+        assert_eq!((options.width, options.height), (640, 320));
+        assert!(capture.is_none());
+        assert_eq!(run("run ch_4_4").0, "ch_4_4");
+
+        assert!(matches!(parse_str(""), Ok(Command::Help)));
+        assert!(matches!(parse_str("list"), Ok(Command::List)));
+        assert_eq!(error("run"), "run expects a chapter, see `gltut list`");
+        assert_eq!(error("ch_1 ch_2"), "Unexpected arguments: ch_1 ch_2");
+        assert_eq!(error("ch_1 --bogus"), "Unknown option --bogus");
+    }
+
+    #[test]
+    fn size_must_be_positive() {
+        let message = "--width and --height must be greater than 0";
+        assert_eq!(error("ch_1 --width 0"), message);
+        assert_eq!(error("ch_1 --height 0 --headless"), message);
+        assert_eq!(error("ch_1 --width -3"), "--width expects a number, got -3");
+        assert_eq!(error("ch_1 --height"), "--height expects a value");
+    }
+
+    #[test]
+    fn headless_conflicts() {
+        let message = "--headless already saves every frame to --out";
+        assert_eq!(error("ch_1 --headless --record frames"), message);
+        assert_eq!(error("ch_1 --screenshot --headless"), message);
+        assert_eq!(
+            error("ch_1 --headless --freeze 1"),
+            "--headless takes the time of its first frame from --time"
+        );
+        assert_eq!(error("demo --headless"), "demo can't run headless");
+
+        let (_, _, capture) = run("ch_1 --headless --frames 3 --time 1.25");
+        let capture = capture.expect("--headless captures");
+        assert_eq!((capture.frames, capture.start_time), (3, 1.25));
+    }
+
+    #[test]
+    fn shape_only_goes_with_view() {
+        assert_eq!(error("ch_1 --shape cube"), "--shape only goes with view");
+        assert_eq!(
+            error("view model.obj --shape cube"),
+            "view takes a mesh file or --shape, not both"
+        );
+        assert_eq!(error("view"), "view expects a mesh file or --shape");
+        assert!(error("view --shape blob")
+            .starts_with("--shape expects one of cube, sphere, icosphere,"));
+
+        match parse_str("view --shape cube") {
+            Ok(Command::View {
+                model: Model::Shape(_),
+                ..
+            }) => {}
+            _ => panic!("expected a shape to view"),
+        }
+        match parse_str("view model.obj") {
+            Ok(Command::View {
+                model: Model::File(path),
+                ..
+            }) => assert_eq!(path, PathBuf::from("model.obj")),
+            _ => panic!("expected a file to view"),
+        }
+    }
+
+    #[test]
+    fn gl_debug_options_imply_gl_debug() {
+        assert!(run("ch_1").1.debug.is_none());
+        assert!(run("ch_1 --gl-debug").1.debug.is_some());
+
+        let debug = run("ch_1 --gl-debug-severity high").1.debug.unwrap();
+        assert_eq!(debug.min_severity, gl::DEBUG_SEVERITY_HIGH);
+        assert!(!debug.panic_on_error);
+
+        let debug = run("ch_1 --gl-debug-sources api,shader-compiler --gl-debug-panic")
+            .1
+            .debug
+            .unwrap();
+        assert_eq!(
+            debug.sources,
+            vec![gl::DEBUG_SOURCE_API, gl::DEBUG_SOURCE_SHADER_COMPILER]
+        );
+        assert!(debug.panic_on_error);
+
+        assert_eq!(
+            error("ch_1 --gl-debug-types error,typo"),
+            "--gl-debug-types expects one of error, deprecated, undefined, portability, \
+             performance, marker, push-group, pop-group, other, got typo"
+        );
+    }
+}
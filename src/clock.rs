@@ -0,0 +1,278 @@
+//! Where tutorial time comes from.
+//!
+//! The runners hand every frame the time of a [`Clock`] instead of reading
+//! glfw's timer, so an animation can be reproduced: headless captures step a
+//! fixed amount per frame from an exact start time, and a [`ManualClock`] can
+//! be set to exact times such as 1.25s.
+use std::time::Instant;
+
+pub trait Clock {
+    /// Tutorial time of the current frame, in seconds.
+    fn time(&self) -> f64;
+
+    /// Move on to the next frame.
+    fn tick(&mut self);
+
+    fn set_paused(&mut self, paused: bool);
+
+    fn is_paused(&self) -> bool;
+
+    /// How fast tutorial time passes relative to the clock's own; 2 runs the
+    /// animations at double speed.
+    fn set_scale(&mut self, scale: f64);
+
+    fn scale(&self) -> f64;
+
+    /// Multiply the scale by `factor`, as `[` and `]` do with 0.5 and 2.
+    fn scale_by(&mut self, factor: f64) {
+        self.set_scale(self.scale() * factor)
+    }
+
+    /// Stop time from passing until `resume`.
+    fn pause(&mut self) {
+        self.set_paused(true)
+    }
+
+    fn resume(&mut self) {
+        self.set_paused(false)
+    }
+}
+
+/// The state every clock shares: the current time, and whether and how fast
+/// it moves.
+struct Timeline {
+    time: f64,
+    scale: f64,
+    paused: bool,
+}
+
+impl Timeline {
+    fn new(time: f64) -> Timeline {
+        Timeline {
+            time,
+            scale: 1.0,
+            paused: false,
+        }
+    }
+
+    /// Let `seconds` of the clock's own time pass.
+    fn advance(&mut self, seconds: f64) {
+        if !self.paused {
+            self.time += seconds * self.scale;
+        }
+    }
+}
+
+macro_rules! timeline_accessors {
+    () => {
+        fn set_paused(&mut self, paused: bool) {
+            self.timeline.paused = paused;
+        }
+
+        fn is_paused(&self) -> bool {
+            self.timeline.paused
+        }
+
+        fn set_scale(&mut self, scale: f64) {
+            self.timeline.scale = scale;
+        }
+
+        fn scale(&self) -> f64 {
+            self.timeline.scale
+        }
+    };
+}
+
+/// Follows the wall clock, starting at 0 when created. Time spent paused is
+/// skipped rather than caught up on.
+pub struct RealTimeClock {
+    timeline: Timeline,
+    last_tick: Instant,
+}
+
+impl RealTimeClock {
+    pub fn new() -> RealTimeClock {
+        RealTimeClock {
+            timeline: Timeline::new(0.0),
+            last_tick: Instant::now(),
+        }
+    }
+}
+
+impl Clock for RealTimeClock {
+    timeline_accessors!();
+
+    fn time(&self) -> f64 {
+        self.timeline.time
+    }
+
+    fn tick(&mut self) {
+        let now = Instant::now();
+        self.timeline
+            .advance(now.duration_since(self.last_tick).as_secs_f64());
+        self.last_tick = now;
+    }
+}
+
+/// Moves `step` seconds per frame however long the frame really took, so
+/// frame `n` always shows `start + n * step`.
+pub struct FixedStepClock {
+    /// Counts steps rather than seconds, so the time doesn't pick up
+    /// rounding errors from adding up `step` over and over.
+    timeline: Timeline,
+    start: f64,
+    step: f64,
+}
+
+impl FixedStepClock {
+    pub fn new(start: f64, step: f64) -> FixedStepClock {
+        FixedStepClock {
+            timeline: Timeline::new(0.0),
+            start,
+            step,
+        }
+    }
+}
+
+impl Clock for FixedStepClock {
+    timeline_accessors!();
+
+    fn time(&self) -> f64 {
+        self.start + self.timeline.time * self.step
+    }
+
+    fn tick(&mut self) {
+        self.timeline.advance(1.0);
+    }
+}
+
+/// Only moves when told to. `tick` does nothing.
+pub struct ManualClock {
+    timeline: Timeline,
+}
+
+impl ManualClock {
+    pub fn new(start: f64) -> ManualClock {
+        ManualClock {
+            timeline: Timeline::new(start),
+        }
+    }
+
+    /// Jump straight to `time`, even while paused.
+    pub fn set(&mut self, time: f64) {
+        self.timeline.time = time;
+    }
+}
+
+impl Clock for ManualClock {
+    timeline_accessors!();
+
+    fn time(&self) -> f64 {
+        self.timeline.time
+    }
+
+    fn tick(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn fixed_step_frames() {
+        let mut clock = FixedStepClock::new(1.25, 0.1);
+        assert_eq!(clock.time(), 1.25);
+        for _ in 0..1000 {
+            clock.tick();
+        }
+        // Exactly, not 1000 additions of 0.1.
+        assert_eq!(clock.time(), 1.25 + 1000.0 * 0.1);
+    }
+
+    #[test]
+    fn fixed_step_pause() {
+        let mut clock = FixedStepClock::new(0.0, 0.5);
+        clock.tick();
+        clock.pause();
+        assert!(clock.is_paused());
+        for _ in 0..5 {
+            clock.tick();
+        }
+        assert_eq!(clock.time(), 0.5);
+        clock.resume();
+        assert!(!clock.is_paused());
+        clock.tick();
+        assert_eq!(clock.time(), 1.0);
+    }
+
+    #[test]
+    fn fixed_step_scale() {
+        let mut clock = FixedStepClock::new(0.0, 0.25);
+        // `]`
+        clock.scale_by(2.0);
+        assert_eq!(clock.scale(), 2.0);
+        clock.tick();
+        assert_eq!(clock.time(), 0.5);
+        // `[` twice
+        clock.scale_by(0.5);
+        clock.scale_by(0.5);
+        assert_eq!(clock.scale(), 0.5);
+        clock.tick();
+        assert_eq!(clock.time(), 0.625);
+        // The scale doesn't touch what has already passed.
+        clock.set_scale(0.0);
+        clock.tick();
+        assert_eq!(clock.time(), 0.625);
+    }
+
+    #[test]
+    fn manual() {
+        let mut clock = ManualClock::new(1.25);
+        clock.tick();
+        assert_eq!(clock.time(), 1.25);
+        clock.set(3.0);
+        assert_eq!(clock.time(), 3.0);
+        // Setting is exact: neither the scale nor pausing gets in the way.
+        clock.set_scale(2.0);
+        clock.pause();
+        clock.set(0.5);
+        clock.tick();
+        assert_eq!(clock.time(), 0.5);
+    }
+
+    #[test]
+    fn real_time() {
+        let mut clock = RealTimeClock::new();
+        assert_eq!(clock.time(), 0.0);
+        thread::sleep(Duration::from_millis(20));
+        clock.tick();
+        let running = clock.time();
+        assert!(running >= 0.02, "{}", running);
+
+        // Time spent paused is skipped, not caught up on after `resume`.
+        clock.pause();
+        thread::sleep(Duration::from_millis(50));
+        clock.tick();
+        assert_eq!(clock.time(), running);
+        clock.resume();
+        clock.tick();
+        assert!(clock.time() - running < 0.05, "{}", clock.time());
+    }
+
+    #[test]
+    fn real_time_scale() {
+        let mut clock = RealTimeClock::new();
+        clock.set_scale(0.0);
+        thread::sleep(Duration::from_millis(10));
+        clock.tick();
+        assert_eq!(clock.time(), 0.0);
+
+        clock.set_scale(4.0);
+        thread::sleep(Duration::from_millis(10));
+        clock.tick();
+        assert!(clock.time() >= 0.04, "{}", clock.time());
+    }
+}
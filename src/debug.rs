@@ -0,0 +1,237 @@
+//! Reporting of GL errors and warnings through `KHR_debug`.
+//!
+//! Without it a bad call, e.g. a wrong offset in `glVertexAttribPointer`,
+//! just draws garbage. With a debug callback the driver tells us about it,
+//! synchronously, so a backtrace taken in the callback points at the call.
+//! Where the extension is missing, debug builds check `glGetError` after
+//! every GL call instead.
+extern crate gl;
+use self::gl::types::*;
+
+use std::backtrace::Backtrace;
+use std::ffi::{c_void, CStr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// The error the callback saw with `panic_on_error` set, for the Rust side
+/// to panic with: a panic can't unwind out of the `extern "system"` callback.
+static FAILURE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Names for the command line and the messages, in the order of the enums.
+pub const SOURCES: &[(&str, GLenum)] = &[
+    ("api", gl::DEBUG_SOURCE_API),
+    ("window-system", gl::DEBUG_SOURCE_WINDOW_SYSTEM),
+    ("shader-compiler", gl::DEBUG_SOURCE_SHADER_COMPILER),
+    ("third-party", gl::DEBUG_SOURCE_THIRD_PARTY),
+    ("application", gl::DEBUG_SOURCE_APPLICATION),
+    ("other", gl::DEBUG_SOURCE_OTHER),
+];
+
+pub const TYPES: &[(&str, GLenum)] = &[
+    ("error", gl::DEBUG_TYPE_ERROR),
+    ("deprecated", gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR),
+    ("undefined", gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR),
+    ("portability", gl::DEBUG_TYPE_PORTABILITY),
+    ("performance", gl::DEBUG_TYPE_PERFORMANCE),
+    ("marker", gl::DEBUG_TYPE_MARKER),
+    ("push-group", gl::DEBUG_TYPE_PUSH_GROUP),
+    ("pop-group", gl::DEBUG_TYPE_POP_GROUP),
+    ("other", gl::DEBUG_TYPE_OTHER),
+];
+
+/// Least severe first.
+pub const SEVERITIES: &[(&str, GLenum)] = &[
+    ("notification", gl::DEBUG_SEVERITY_NOTIFICATION),
+    ("low", gl::DEBUG_SEVERITY_LOW),
+    ("medium", gl::DEBUG_SEVERITY_MEDIUM),
+    ("high", gl::DEBUG_SEVERITY_HIGH),
+];
+
+/// Which debug messages to report, and what to do about errors.
+#[derive(Clone, Debug)]
+pub struct DebugOutput {
+    /// Only report messages from these sources; all of them if empty.
+    pub sources: Vec<GLenum>,
+    /// Only report messages of these types; all of them if empty.
+    pub types: Vec<GLenum>,
+    /// The least severe messages that are still reported.
+    pub min_severity: GLenum,
+    /// Panic on the first high-severity error. The callback prints the
+    /// backtrace; the panic follows once GL has returned.
+    pub panic_on_error: bool,
+}
+
+impl Default for DebugOutput {
+    fn default() -> DebugOutput {
+        DebugOutput {
+            sources: Vec::new(),
+            types: Vec::new(),
+            min_severity: gl::DEBUG_SEVERITY_LOW,
+            panic_on_error: false,
+        }
+    }
+}
+
+impl DebugOutput {
+    fn wants(&self, source: GLenum, kind: GLenum, severity: GLenum) -> bool {
+        (self.sources.is_empty() || self.sources.contains(&source))
+            && (self.types.is_empty() || self.types.contains(&kind))
+            && rank(severity) >= rank(self.min_severity)
+    }
+}
+
+/// The error that should have panicked since the last call, if any.
+pub fn take_failure() -> Option<String> {
+    FAILURE.lock().unwrap_or_else(|err| err.into_inner()).take()
+}
+
+/// Whether the `glGetError` poll panics; `after_each_call` takes no state.
+static POLL_PANICS: AtomicBool = AtomicBool::new(false);
+
+/// Report every error the command raised. Only debug builds call this.
+fn poll(command: &'static str) {
+    loop {
+        let error = unsafe { gl::GetError() };
+        if error == gl::NO_ERROR {
+            break;
+        }
+        let message = format!("GL error {} in gl{}", error_name(error), command);
+        if POLL_PANICS.load(Ordering::Relaxed) {
+            panic!("{}\n{}", message, Backtrace::force_capture());
+        }
+        eprintln!("{}", message);
+    }
+}
+
+/// Panic right after the command the callback failed in. Only debug builds
+/// call this; release builds panic in the framework after the whole step.
+fn fail_after(command: &'static str) {
+    if let Some(message) = take_failure() {
+        panic!("{} (raised by gl{})", message, command);
+    }
+}
+
+/// Route the debug messages of the current context through `output`. When
+/// `KHR_debug` isn't available, debug builds check `glGetError` after every
+/// GL call instead.
+pub unsafe fn install(output: &DebugOutput) {
+    let khr = version() >= (4, 3) || has_extension("GL_KHR_debug");
+    if !(khr || has_extension("GL_ARB_debug_output")) || !gl::DebugMessageCallback::is_loaded() {
+        if cfg!(debug_assertions) {
+            eprintln!("Warning: GL_KHR_debug isn't available, checking glGetError instead");
+            POLL_PANICS.store(output.panic_on_error, Ordering::Relaxed);
+            gl::after_each_call(Some(poll));
+        } else {
+            eprintln!("Warning: GL_KHR_debug isn't available, GL errors won't be reported");
+        }
+        return;
+    }
+
+    let mut flags = 0;
+    gl::GetIntegerv(gl::CONTEXT_FLAGS, &mut flags);
+    if flags as GLenum & gl::CONTEXT_FLAG_DEBUG_BIT == 0 {
+        eprintln!("Warning: not a debug context, the driver may report less");
+    }
+
+    // ARB_debug_output has no switch; it is always on in a debug context
+    if khr {
+        gl::Enable(gl::DEBUG_OUTPUT);
+    }
+    // call back from inside the offending call, so backtraces are useful
+    gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+    // low severity messages start out disabled; we filter them ourselves
+    gl::DebugMessageControl(
+        gl::DONT_CARE,
+        gl::DONT_CARE,
+        gl::DONT_CARE,
+        0,
+        std::ptr::null(),
+        gl::TRUE,
+    );
+    // The callback can fire until the context is gone, so the filter has to
+    // outlive it; it is small and there is one per process.
+    let output = Box::into_raw(Box::new(output.clone()));
+    gl::DebugMessageCallback(Some(callback), output as *const c_void);
+    gl::after_each_call(Some(fail_after));
+}
+
+extern "system" fn callback(
+    source: GLenum,
+    kind: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    message: *const GLchar,
+    output: *mut c_void,
+) {
+    let output = unsafe { &*(output as *const DebugOutput) };
+    if !output.wants(source, kind, severity) {
+        return;
+    }
+    let message = if length >= 0 {
+        let bytes = unsafe { std::slice::from_raw_parts(message as *const u8, length as usize) };
+        String::from_utf8_lossy(bytes).into_owned()
+    } else {
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    };
+    let message = format!(
+        "GL {} {} from {} ({}): {}",
+        name(SEVERITIES, severity),
+        name(TYPES, kind),
+        name(SOURCES, source),
+        id,
+        message.trim_end()
+    );
+    if output.panic_on_error && kind == gl::DEBUG_TYPE_ERROR && severity == gl::DEBUG_SEVERITY_HIGH
+    {
+        // The backtrace has to be taken here, inside the offending call.
+        eprintln!("{}\n{}", message, Backtrace::force_capture());
+        let mut failure = FAILURE.lock().unwrap_or_else(|err| err.into_inner());
+        failure.get_or_insert(message);
+        return;
+    }
+    eprintln!("{}", message);
+}
+
+fn name(table: &[(&'static str, GLenum)], value: GLenum) -> String {
+    table
+        .iter()
+        .find(|(_, v)| *v == value)
+        .map_or_else(|| format!("0x{:04x}", value), |(name, _)| name.to_string())
+}
+
+fn rank(severity: GLenum) -> usize {
+    SEVERITIES
+        .iter()
+        .position(|(_, s)| *s == severity)
+        .unwrap_or(SEVERITIES.len())
+}
+
+fn error_name(error: GLenum) -> String {
+    match error {
+        gl::INVALID_ENUM => "GL_INVALID_ENUM".to_string(),
+        gl::INVALID_VALUE => "GL_INVALID_VALUE".to_string(),
+        gl::INVALID_OPERATION => "GL_INVALID_OPERATION".to_string(),
+        gl::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION".to_string(),
+        gl::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY".to_string(),
+        _ => format!("0x{:04x}", error),
+    }
+}
+
+unsafe fn version() -> (i32, i32) {
+    let (mut major, mut minor) = (0, 0);
+    gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+    gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    (major, minor)
+}
+
+unsafe fn has_extension(extension: &str) -> bool {
+    let mut count = 0;
+    gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+    (0..count as u32).any(|index| {
+        let name = gl::GetStringi(gl::EXTENSIONS, index);
+        !name.is_null() && CStr::from_ptr(name as *const _).to_bytes() == extension.as_bytes()
+    })
+}
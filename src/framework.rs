@@ -0,0 +1,517 @@
+//! Shared glfw/GL boilerplate for the tutorials.
+//!
+//! Every chapter used to repeat the same window setup, render loop and
+//! `process_events`. A chapter now implements [`Tutorial`] with just its
+//! scene code and hands itself to [`run`], which owns the window and the
+//! event loop (the same split as the `framework.cpp` from the C++ gltut).
+//! Because every chapter is split into `init`, `display` and `teardown`,
+//! one window can also switch between chapters while it runs.
+extern crate glfw;
+use self::glfw::{Action, Context, Key};
+
+extern crate gl;
+
+use crate::capture::{self, Image};
+use crate::clock::{Clock, FixedStepClock, ManualClock, RealTimeClock};
+use crate::debug::{self, DebugOutput};
+use crate::loaders::LoadError;
+use crate::mesh::MeshError;
+use crate::shader::{Shader, ShaderError};
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::Receiver;
+
+/// Command line settings that apply to whichever tutorial is run.
+pub struct Options {
+    /// Size of the window, or of the offscreen framebuffer when headless.
+    pub width: u32,
+    pub height: u32,
+    /// Open the window fullscreen on the primary monitor.
+    pub fullscreen: bool,
+    /// Wait for vertical sync when swapping buffers.
+    pub vsync: bool,
+    /// Samples per pixel for multisampling, 0 to turn it off.
+    pub samples: u32,
+    /// Speed of tutorial time, 1 for real time.
+    pub time_scale: f64,
+    /// Advance the window's clock by this many seconds per frame instead of
+    /// following the wall clock.
+    pub fixed_step: Option<f64>,
+    /// Hold the window's clock at this time; `,` and `.` step it a frame
+    /// back or forward.
+    pub freeze: Option<f64>,
+    /// Print the attributes, uniforms and blocks of every shader program
+    /// once the tutorial is initialized.
+    pub dump_shader_info: bool,
+    /// Ask for a debug context and report GL's debug messages.
+    pub debug: Option<DebugOutput>,
+    /// Take a screenshot of the first frame, as if F12 had been pressed.
+    pub screenshot: bool,
+    /// Save the first frames the window shows as an image sequence.
+    pub record: Option<Recording>,
+}
+
+pub struct Recording {
+    pub dir: PathBuf,
+    pub frames: u32,
+}
+
+/// How far `,` and `.` move a frozen clock.
+const FROZEN_STEP: f64 = 1.0 / 60.0;
+
+/// Where F12 puts its screenshots.
+const SCREENSHOT_DIR: &str = "screenshots";
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            width: 800,
+            height: 600,
+            fullscreen: false,
+            vsync: true,
+            samples: 0,
+            time_scale: 1.0,
+            fixed_step: None,
+            freeze: None,
+            dump_shader_info: false,
+            debug: None,
+            screenshot: false,
+            record: None,
+        }
+    }
+}
+
+pub trait Tutorial {
+    /// Called once, after the GL context is current and the function pointers
+    /// are loaded. Build shaders, buffers and vertex arrays here.
+    fn init() -> Result<Self, InitError>
+    where
+        Self: Sized;
+
+    /// Draw a single frame. `time` is the runner's clock in seconds, see
+    /// [`Clock`].
+    fn display(&mut self, time: f64);
+
+    /// Called whenever the framebuffer changes size.
+    fn reshape(&mut self, width: i32, height: i32) {
+        // make sure the viewport matches the new window dimensions; note that width and
+        // height will be significantly larger than specified on retina displays.
+        unsafe { gl::Viewport(0, 0, width, height) }
+    }
+
+    /// Called for every key event except Escape, which always closes the window.
+    fn keyboard(&mut self, _key: Key, _action: Action, _modifiers: glfw::Modifiers) {}
+
+    /// Called for every mouse button event, with the cursor position at the
+    /// time of the event.
+    fn mouse(
+        &mut self,
+        _button: glfw::MouseButton,
+        _action: Action,
+        _modifiers: glfw::Modifiers,
+        _position: (f64, f64),
+    ) {
+    }
+
+    /// The file-backed shaders of this tutorial. The runner rebuilds them
+    /// when their sources change on disk, or on demand with F5.
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        Vec::new()
+    }
+
+    /// Called before the tutorial is dropped, while its context is still
+    /// current. Put back any GL state `init` changed, so the next chapter
+    /// starts clean. GL objects owned through [`crate::objects`] and
+    /// [`Shader`] delete themselves when the tutorial is dropped.
+    fn teardown(&mut self) {}
+}
+
+/// Why a tutorial couldn't be initialized.
+#[derive(Debug)]
+pub enum InitError {
+    Shader(ShaderError),
+    /// A mesh file the tutorial draws couldn't be loaded.
+    Load(LoadError),
+    /// Mesh data built in code doesn't fit together.
+    Mesh(MeshError),
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InitError::Shader(err) => write!(f, "{}", err),
+            InitError::Load(err) => write!(f, "{}", err),
+            InitError::Mesh(err) => write!(f, "Failed to build a mesh: {}", err),
+        }
+    }
+}
+
+impl Error for InitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InitError::Shader(err) => Some(err),
+            InitError::Load(err) => Some(err),
+            InitError::Mesh(err) => Some(err),
+        }
+    }
+}
+
+impl From<ShaderError> for InitError {
+    fn from(err: ShaderError) -> InitError {
+        InitError::Shader(err)
+    }
+}
+
+impl From<LoadError> for InitError {
+    fn from(err: LoadError) -> InitError {
+        InitError::Load(err)
+    }
+}
+
+impl From<MeshError> for InitError {
+    fn from(err: MeshError) -> InitError {
+        InitError::Mesh(err)
+    }
+}
+
+/// A chapter that can be picked on the command line or switched to.
+pub struct Chapter {
+    pub key: &'static str,
+    pub description: &'static str,
+    pub init: fn() -> Result<Box<dyn Tutorial>, InitError>,
+}
+
+/// `init` for a `Chapter` table entry.
+pub fn boxed<T: Tutorial + 'static>() -> Result<Box<dyn Tutorial>, InitError> {
+    Ok(Box::new(T::init()?))
+}
+
+/// Open a window and run `chapters[first]` until the window is closed.
+/// With more than one chapter, PageUp/PageDown move to the previous/next one
+/// and the number keys jump to the first part of that chapter. P pauses and
+/// resumes time, `[` and `]` halve and double its speed, and F12 saves a
+/// screenshot.
+pub fn run(options: &Options, chapters: &[Chapter], first: usize) {
+    // glfw: initialize and configure
+    // ------------------------------
+    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
+    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+        glfw::OpenGlProfileHint::Core,
+    ));
+    #[cfg(target_os = "macos")]
+    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+    // The depth buffer chapters need one. 24 bits is the usual default, but
+    // not one to rely on, and it's what the headless framebuffer has.
+    glfw.window_hint(glfw::WindowHint::DepthBits(Some(24)));
+    if options.samples > 0 {
+        glfw.window_hint(glfw::WindowHint::Samples(Some(options.samples)));
+    }
+    if options.debug.is_some() {
+        glfw.window_hint(glfw::WindowHint::OpenGlDebugContext(true));
+    }
+
+    // glfw window creation
+    // --------------------
+    let (mut window, events) = glfw
+        .with_primary_monitor(|glfw, monitor| {
+            let mode = match monitor {
+                Some(monitor) if options.fullscreen => glfw::WindowMode::FullScreen(monitor),
+                _ => glfw::WindowMode::Windowed,
+            };
+            glfw.create_window(options.width, options.height, "LearnOpenGL", mode)
+        })
+        .expect("Failed to create GLFW window");
+
+    window.make_current();
+    glfw.set_swap_interval(if options.vsync {
+        glfw::SwapInterval::Sync(1)
+    } else {
+        glfw::SwapInterval::None
+    });
+    window.set_key_polling(true);
+    window.set_mouse_button_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    // gl: load all OpenGL function pointers
+    // ---------------------------------------
+    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+    if options.samples > 0 {
+        unsafe { gl::Enable(gl::MULTISAMPLE) };
+    }
+    install_debug_output(options);
+
+    let mut running: Box<dyn Clock> = match options.fixed_step {
+        Some(step) => Box::new(FixedStepClock::new(0.0, step)),
+        None => Box::new(RealTimeClock::new()),
+    };
+    running.set_scale(options.time_scale);
+    let mut frozen = options.freeze.map(ManualClock::new);
+
+    if let Some(record) = &options.record {
+        fs::create_dir_all(&record.dir)
+            .unwrap_or_else(|err| panic!("Failed to create {:?}: {}", record.dir, err));
+    }
+    let mut screenshot = options.screenshot;
+    let mut recorded = 0;
+
+    let mut current = first;
+    let mut tutorial = init_or_exit(&chapters[current], options);
+    show(&mut window, &chapters[current], tutorial.as_mut());
+    check_errors(&chapters[current], "init");
+
+    // render loop
+    // -----------
+    while !window.should_close() {
+        // events
+        // -----
+        let requests = process_events(
+            &mut window,
+            &events,
+            tutorial.as_mut(),
+            current_clock(&mut frozen, &mut running),
+            chapters,
+            current,
+        );
+        screenshot |= requests.screenshot;
+        if let Some(frozen) = frozen.as_mut().filter(|_| requests.frames != 0) {
+            frozen.set(frozen.time() + f64::from(requests.frames) * FROZEN_STEP);
+            println!("At {:.3}s", frozen.time());
+        }
+        if let Some(next) = requests.chapter.filter(|next| *next != current) {
+            tutorial = switch(tutorial, &chapters[current], &chapters[next], options);
+            current = next;
+            show(&mut window, &chapters[current], tutorial.as_mut());
+        }
+        for shader in tutorial.shaders() {
+            shader.reload_if_changed();
+        }
+        check_errors(&chapters[current], "events");
+
+        // render
+        // ------
+        tutorial.display(current_clock(&mut frozen, &mut running).time());
+        check_errors(&chapters[current], "display");
+
+        if screenshot {
+            let name = format!("{}_{}.png", chapters[current].key, capture::timestamp());
+            let dir = Path::new(SCREENSHOT_DIR);
+            if let Err(err) = fs::create_dir_all(dir) {
+                eprintln!("Failed to create {:?}: {}", dir, err);
+            } else {
+                save_window(&window, &dir.join(name));
+            }
+            screenshot = false;
+        }
+        if let Some(record) = options.record.as_ref().filter(|r| recorded < r.frames) {
+            save_window(
+                &window,
+                &record.dir.join(format!("frame_{:04}.png", recorded)),
+            );
+            recorded += 1;
+            if recorded == record.frames {
+                println!("Recorded {} frames to {}", recorded, record.dir.display());
+            }
+        }
+
+        // glfw: swap buffers and poll IO events (keys pressed/released, mouse moved etc.)
+        // -------------------------------------------------------------------------------
+        window.swap_buffers();
+        glfw.poll_events();
+        current_clock(&mut frozen, &mut running).tick();
+    }
+
+    tutorial.teardown();
+    check_errors(&chapters[current], "teardown");
+}
+
+/// Hook GL's debug messages up to `options.debug`, if it is set.
+pub fn install_debug_output(options: &Options) {
+    if let Some(output) = &options.debug {
+        unsafe { debug::install(output) }
+    }
+}
+
+/// Panic with the error the debug callback couldn't panic with, if debug
+/// builds haven't already after the call that raised it.
+pub fn check_errors(chapter: &Chapter, stage: &str) {
+    if let Some(message) = debug::take_failure() {
+        panic!("{} during {} {}", message, chapter.key, stage);
+    }
+}
+
+/// Save what the window is about to show to `path`.
+fn save_window(window: &glfw::Window, path: &Path) {
+    let (width, height) = window.get_framebuffer_size();
+    let image = Image::read_window(width as u32, height as u32);
+    match image.save(path) {
+        Ok(()) => println!("Wrote {}", path.display()),
+        Err(err) => eprintln!("Failed to write {:?}: {}", path, err),
+    }
+}
+
+/// Tear `tutorial` down and set up `to` in its place. If `to` fails to
+/// build, `from` is set up again so there is still something to look at.
+fn switch(
+    mut tutorial: Box<dyn Tutorial>,
+    from: &Chapter,
+    to: &Chapter,
+    options: &Options,
+) -> Box<dyn Tutorial> {
+    tutorial.teardown();
+    drop(tutorial);
+    match init(to, options) {
+        Ok(tutorial) => tutorial,
+        Err(err) => {
+            eprintln!("{}\nStaying on {}", err, from.key);
+            init_or_exit(from, options)
+        }
+    }
+}
+
+/// Name the chapter in the title bar and tell it how big the window is.
+fn show(window: &mut glfw::Window, chapter: &Chapter, tutorial: &mut dyn Tutorial) {
+    window.set_title(&format!("LearnOpenGL - {}", chapter.key));
+    let (width, height) = window.get_framebuffer_size();
+    tutorial.reshape(width, height);
+}
+
+fn init(chapter: &Chapter, options: &Options) -> Result<Box<dyn Tutorial>, InitError> {
+    let mut tutorial = (chapter.init)()?;
+    if options.dump_shader_info {
+        dump_shader_info(tutorial.as_mut());
+    }
+    Ok(tutorial)
+}
+
+/// Initialize `chapter`, or report why it couldn't be and exit. There is
+/// nothing sensible to draw without the shaders and meshes it needs.
+pub fn init_or_exit(chapter: &Chapter, options: &Options) -> Box<dyn Tutorial> {
+    init(chapter, options).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1)
+    })
+}
+
+fn dump_shader_info(tutorial: &mut dyn Tutorial) {
+    for shader in tutorial.shaders() {
+        println!(
+            "Program {} ({})",
+            shader.program().id(),
+            shader.paths().join(", ")
+        );
+        print!("{}", shader.info());
+    }
+}
+
+/// The chapter number in a key like `ch_5_1`.
+fn chapter_number(key: &str) -> Option<u32> {
+    key.strip_prefix("ch_")?.split('_').next()?.parse().ok()
+}
+
+/// Where a chapter switching key leads, if anywhere.
+fn switch_target(key: Key, chapters: &[Chapter], current: usize) -> Option<usize> {
+    let number = match key {
+        Key::PageUp => return Some((current + chapters.len() - 1) % chapters.len()),
+        Key::PageDown => return Some((current + 1) % chapters.len()),
+        Key::Num1 | Key::Kp1 => 1,
+        Key::Num2 | Key::Kp2 => 2,
+        Key::Num3 | Key::Kp3 => 3,
+        Key::Num4 | Key::Kp4 => 4,
+        Key::Num5 | Key::Kp5 => 5,
+        Key::Num6 | Key::Kp6 => 6,
+        Key::Num7 | Key::Kp7 => 7,
+        Key::Num8 | Key::Kp8 => 8,
+        Key::Num9 | Key::Kp9 => 9,
+        _ => return None,
+    };
+    chapters
+        .iter()
+        .position(|chapter| chapter_number(chapter.key) == Some(number))
+}
+
+/// What the keys pressed since the last frame ask the runner to do.
+#[derive(Default)]
+struct Requests {
+    /// Switch to this chapter.
+    chapter: Option<usize>,
+    screenshot: bool,
+    /// Frames to step a frozen clock by, negative to go back.
+    frames: i32,
+}
+
+/// The frozen clock if there is one, otherwise the running one.
+fn current_clock<'a>(
+    frozen: &'a mut Option<ManualClock>,
+    running: &'a mut Box<dyn Clock>,
+) -> &'a mut dyn Clock {
+    match frozen {
+        Some(frozen) => frozen,
+        None => running.as_mut(),
+    }
+}
+
+/// Hand the pending events to `tutorial`, and collect the ones meant for the
+/// runner.
+fn process_events(
+    window: &mut glfw::Window,
+    events: &Receiver<(f64, glfw::WindowEvent)>,
+    tutorial: &mut dyn Tutorial,
+    clock: &mut dyn Clock,
+    chapters: &[Chapter],
+    current: usize,
+) -> Requests {
+    let mut requests = Requests::default();
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => tutorial.reshape(width, height),
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::F5, _, Action::Press, _) => {
+                for shader in tutorial.shaders() {
+                    shader.reload();
+                }
+            }
+            glfw::WindowEvent::Key(Key::F12, _, Action::Press, _) => requests.screenshot = true,
+            glfw::WindowEvent::Key(Key::P, _, Action::Press, _) => {
+                if clock.is_paused() {
+                    clock.resume();
+                    println!("Resumed at {:.3}s", clock.time());
+                } else {
+                    clock.pause();
+                    println!("Paused at {:.3}s", clock.time());
+                }
+            }
+            glfw::WindowEvent::Key(key @ Key::Comma, _, Action::Press, _)
+            | glfw::WindowEvent::Key(key @ Key::Period, _, Action::Press, _) => {
+                requests.frames += if key == Key::Comma { -1 } else { 1 };
+            }
+            glfw::WindowEvent::Key(key @ Key::LeftBracket, _, Action::Press, _)
+            | glfw::WindowEvent::Key(key @ Key::RightBracket, _, Action::Press, _) => {
+                let factor = if key == Key::LeftBracket { 0.5 } else { 2.0 };
+                clock.scale_by(factor);
+                println!("Time scale {}", clock.scale());
+            }
+            glfw::WindowEvent::Key(key, _, action, modifiers) => {
+                let target = match action {
+                    Action::Press if chapters.len() > 1 => switch_target(key, chapters, current),
+                    _ => None,
+                };
+                match target {
+                    Some(_) => requests.chapter = target,
+                    None => tutorial.keyboard(key, action, modifiers),
+                }
+            }
+            glfw::WindowEvent::MouseButton(button, action, modifiers) => {
+                tutorial.mouse(button, action, modifiers, window.get_cursor_pos())
+            }
+            _ => {}
+        }
+    }
+    requests
+}
@@ -0,0 +1,207 @@
+//! Just enough of EGL to get a windowless desktop GL context.
+//!
+//! We prefer Mesa's surfaceless platform, which needs neither a display
+//! server nor a GPU (llvmpipe renders in software), and fall back to the
+//! default display when the extension isn't there.
+#![allow(non_camel_case_types)]
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+
+type EGLBoolean = u32;
+type EGLenum = u32;
+type EGLint = i32;
+type EGLDisplay = *mut c_void;
+type EGLConfig = *mut c_void;
+type EGLContext = *mut c_void;
+type EGLSurface = *mut c_void;
+
+type eglGetPlatformDisplayEXT_t = unsafe extern "C" fn(
+    platform: EGLenum,
+    native_display: *mut c_void,
+    attrib_list: *const EGLint,
+) -> EGLDisplay;
+
+const EGL_FALSE: EGLBoolean = 0;
+const EGL_NONE: EGLint = 0x3038;
+const EGL_EXTENSIONS: EGLint = 0x3055;
+const EGL_RED_SIZE: EGLint = 0x3024;
+const EGL_GREEN_SIZE: EGLint = 0x3023;
+const EGL_BLUE_SIZE: EGLint = 0x3022;
+const EGL_ALPHA_SIZE: EGLint = 0x3021;
+const EGL_SURFACE_TYPE: EGLint = 0x3033;
+const EGL_PBUFFER_BIT: EGLint = 0x0001;
+const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+const EGL_OPENGL_BIT: EGLint = 0x0008;
+const EGL_OPENGL_API: EGLenum = 0x30A2;
+const EGL_CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+const EGL_CONTEXT_MINOR_VERSION: EGLint = 0x30FB;
+const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30FD;
+const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x0001;
+const EGL_CONTEXT_OPENGL_DEBUG: EGLint = 0x31B0;
+const EGL_TRUE: EGLint = 1;
+const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31DD;
+
+#[link(name = "EGL")]
+extern "C" {
+    fn eglGetError() -> EGLint;
+    fn eglGetDisplay(display_id: *mut c_void) -> EGLDisplay;
+    fn eglQueryString(dpy: EGLDisplay, name: EGLint) -> *const c_char;
+    fn eglInitialize(dpy: EGLDisplay, major: *mut EGLint, minor: *mut EGLint) -> EGLBoolean;
+    fn eglTerminate(dpy: EGLDisplay) -> EGLBoolean;
+    fn eglBindAPI(api: EGLenum) -> EGLBoolean;
+    fn eglChooseConfig(
+        dpy: EGLDisplay,
+        attrib_list: *const EGLint,
+        configs: *mut EGLConfig,
+        config_size: EGLint,
+        num_config: *mut EGLint,
+    ) -> EGLBoolean;
+    fn eglCreateContext(
+        dpy: EGLDisplay,
+        config: EGLConfig,
+        share_context: EGLContext,
+        attrib_list: *const EGLint,
+    ) -> EGLContext;
+    fn eglDestroyContext(dpy: EGLDisplay, ctx: EGLContext) -> EGLBoolean;
+    fn eglMakeCurrent(
+        dpy: EGLDisplay,
+        draw: EGLSurface,
+        read: EGLSurface,
+        ctx: EGLContext,
+    ) -> EGLBoolean;
+    fn eglGetProcAddress(procname: *const c_char) -> *const c_void;
+}
+
+/// A 3.3 core context made current without any surface attached. Everything
+/// has to be drawn into a framebuffer object. `debug` asks for a debug
+/// context where EGL is new enough (1.5) to know about them.
+pub struct Context {
+    display: EGLDisplay,
+    context: EGLContext,
+}
+
+impl Context {
+    pub fn new_surfaceless(debug: bool) -> Result<Context, String> {
+        unsafe {
+            let display = get_display();
+            if display.is_null() {
+                return Err("No EGL display available".to_string());
+            }
+            let (mut major, mut minor) = (0, 0);
+            if eglInitialize(display, &mut major, &mut minor) == EGL_FALSE {
+                return Err(egl_error("eglInitialize"));
+            }
+            if eglBindAPI(EGL_OPENGL_API) == EGL_FALSE {
+                eglTerminate(display);
+                return Err(egl_error("eglBindAPI"));
+            }
+
+            // The default framebuffer is never used, so the config only has to
+            // exist. Depth lives in the framebuffer object instead.
+            #[rustfmt::skip]
+            let config_attribs = [
+                EGL_SURFACE_TYPE, EGL_PBUFFER_BIT,
+                EGL_RENDERABLE_TYPE, EGL_OPENGL_BIT,
+                EGL_RED_SIZE, 8,
+                EGL_GREEN_SIZE, 8,
+                EGL_BLUE_SIZE, 8,
+                EGL_ALPHA_SIZE, 8,
+                EGL_NONE,
+            ];
+            let mut config = ptr::null_mut();
+            let mut num_configs = 0;
+            if eglChooseConfig(
+                display,
+                config_attribs.as_ptr(),
+                &mut config,
+                1,
+                &mut num_configs,
+            ) == EGL_FALSE
+            {
+                eglTerminate(display);
+                return Err(egl_error("eglChooseConfig"));
+            }
+            if num_configs == 0 {
+                eglTerminate(display);
+                return Err("No EGL config supports desktop OpenGL".to_string());
+            }
+
+            #[rustfmt::skip]
+            let mut context_attribs = vec![
+                EGL_CONTEXT_MAJOR_VERSION, 3,
+                EGL_CONTEXT_MINOR_VERSION, 3,
+                EGL_CONTEXT_OPENGL_PROFILE_MASK, EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+            ];
+            if debug && (major, minor) >= (1, 5) {
+                context_attribs.extend_from_slice(&[EGL_CONTEXT_OPENGL_DEBUG, EGL_TRUE]);
+            }
+            context_attribs.push(EGL_NONE);
+            let context =
+                eglCreateContext(display, config, ptr::null_mut(), context_attribs.as_ptr());
+            if context.is_null() {
+                eglTerminate(display);
+                return Err(egl_error("eglCreateContext"));
+            }
+            if eglMakeCurrent(display, ptr::null_mut(), ptr::null_mut(), context) == EGL_FALSE {
+                eglDestroyContext(display, context);
+                eglTerminate(display);
+                return Err(egl_error("eglMakeCurrent"));
+            }
+
+            Ok(Context { display, context })
+        }
+    }
+
+    pub fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        let symbol = CString::new(symbol).unwrap();
+        unsafe { eglGetProcAddress(symbol.as_ptr()) }
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe {
+            eglMakeCurrent(
+                self.display,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            eglDestroyContext(self.display, self.context);
+            eglTerminate(self.display);
+        }
+    }
+}
+
+unsafe fn get_display() -> EGLDisplay {
+    // Client extensions are queried on EGL_NO_DISPLAY
+    let extensions = eglQueryString(ptr::null_mut(), EGL_EXTENSIONS);
+    let has_surfaceless = !extensions.is_null()
+        && CStr::from_ptr(extensions)
+            .to_string_lossy()
+            .split(' ')
+            .any(|ext| ext == "EGL_MESA_platform_surfaceless");
+
+    if has_surfaceless {
+        let name = CString::new("eglGetPlatformDisplayEXT").unwrap();
+        let get_platform_display = eglGetProcAddress(name.as_ptr());
+        if !get_platform_display.is_null() {
+            let get_platform_display: eglGetPlatformDisplayEXT_t =
+                std::mem::transmute(get_platform_display);
+            return get_platform_display(
+                EGL_PLATFORM_SURFACELESS_MESA,
+                ptr::null_mut(),
+                ptr::null(),
+            );
+        }
+    }
+
+    eglGetDisplay(ptr::null_mut())
+}
+
+fn egl_error(call: &str) -> String {
+    format!("{} failed with EGL error 0x{:x}", call, unsafe {
+        eglGetError()
+    })
+}
@@ -0,0 +1,197 @@
+//! Offscreen rendering for machines without a display or GPU.
+//!
+//! Instead of opening a window we create a surfaceless EGL context, bind a
+//! framebuffer object and let the tutorial draw into it exactly as it would
+//! into the default framebuffer. The first few frames are then written out
+//! as image files.
+#[cfg(target_os = "linux")]
+mod egl;
+
+use crate::capture::Image;
+use crate::clock::{Clock, FixedStepClock};
+use crate::framework::{self, Chapter, Options};
+
+extern crate gl;
+use self::gl::types::*;
+
+use std::fs;
+use std::path::PathBuf;
+
+/// What to render and where to put it.
+pub struct Capture {
+    /// Number of frames to render.
+    pub frames: u32,
+    /// Directory the frames are written to, created if missing.
+    pub out_dir: PathBuf,
+    /// Image file extension, `png` or `ppm`.
+    pub format: String,
+    /// Tutorial time of the first frame, in seconds.
+    pub start_time: f64,
+    /// Seconds of tutorial time between two frames. Frame `n` is drawn at
+    /// `start_time + n * time_step`, so captures don't depend on how fast the
+    /// machine is.
+    pub time_step: f64,
+}
+
+impl Default for Capture {
+    fn default() -> Capture {
+        Capture {
+            frames: 1,
+            out_dir: PathBuf::from("frames"),
+            format: "png".to_string(),
+            start_time: 0.0,
+            time_step: 1.0 / 60.0,
+        }
+    }
+}
+
+/// Color and depth/stencil renderbuffers standing in for the window's
+/// default framebuffer, multisampled if `samples` isn't 0.
+struct Framebuffer {
+    fbo: u32,
+    color: u32,
+    depth_stencil: u32,
+}
+
+impl Framebuffer {
+    unsafe fn new(width: u32, height: u32, samples: u32) -> Framebuffer {
+        let (mut fbo, mut color, mut depth_stencil) = (0, 0, 0);
+
+        gl::GenRenderbuffers(1, &mut color);
+        gl::BindRenderbuffer(gl::RENDERBUFFER, color);
+        gl::RenderbufferStorageMultisample(
+            gl::RENDERBUFFER,
+            samples as GLsizei,
+            gl::RGBA8,
+            width as GLsizei,
+            height as GLsizei,
+        );
+
+        gl::GenRenderbuffers(1, &mut depth_stencil);
+        gl::BindRenderbuffer(gl::RENDERBUFFER, depth_stencil);
+        gl::RenderbufferStorageMultisample(
+            gl::RENDERBUFFER,
+            samples as GLsizei,
+            gl::DEPTH24_STENCIL8,
+            width as GLsizei,
+            height as GLsizei,
+        );
+        gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+        gl::GenFramebuffers(1, &mut fbo);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+        gl::FramebufferRenderbuffer(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            gl::RENDERBUFFER,
+            color,
+        );
+        gl::FramebufferRenderbuffer(
+            gl::FRAMEBUFFER,
+            gl::DEPTH_STENCIL_ATTACHMENT,
+            gl::RENDERBUFFER,
+            depth_stencil,
+        );
+        let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            panic!("Offscreen framebuffer is incomplete: 0x{:x}", status);
+        }
+
+        Framebuffer {
+            fbo,
+            color,
+            depth_stencil,
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteRenderbuffers(1, &self.color);
+            gl::DeleteRenderbuffers(1, &self.depth_stencil);
+        }
+    }
+}
+
+/// Render `capture.frames` frames of `chapter` offscreen and save each one.
+#[cfg(target_os = "linux")]
+pub fn run(capture: &Capture, options: &Options, chapter: &Chapter) {
+    let context = egl::Context::new_surfaceless(options.debug.is_some())
+        .unwrap_or_else(|err| panic!("Failed to create headless GL context: {}", err));
+
+    // gl: load all OpenGL function pointers
+    // ---------------------------------------
+    gl::load_with(|symbol| context.get_proc_address(symbol));
+    framework::install_debug_output(options);
+
+    let (width, height) = (options.width, options.height);
+    let framebuffer = unsafe { Framebuffer::new(width, height, options.samples) };
+    // Multisampled pixels can't be read directly, they have to be resolved
+    // into a plain framebuffer first.
+    let resolve = if options.samples > 0 {
+        let resolve = unsafe { Framebuffer::new(width, height, 0) };
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.fbo) };
+        Some(resolve)
+    } else {
+        None
+    };
+    // A window would get this from its surface; we have to set it ourselves.
+    unsafe { gl::Viewport(0, 0, width as GLsizei, height as GLsizei) };
+
+    fs::create_dir_all(&capture.out_dir)
+        .unwrap_or_else(|err| panic!("Failed to create {:?}: {}", capture.out_dir, err));
+
+    let mut tutorial = framework::init_or_exit(chapter, options);
+    // The window runner does this when it shows the chapter.
+    tutorial.reshape(width as i32, height as i32);
+    framework::check_errors(chapter, "init");
+
+    let mut clock = FixedStepClock::new(capture.start_time, capture.time_step);
+    clock.set_scale(options.time_scale);
+
+    for frame in 0..capture.frames {
+        tutorial.display(clock.time());
+        framework::check_errors(chapter, "display");
+        clock.tick();
+
+        let image = unsafe {
+            gl::Finish();
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer.fbo);
+            if let Some(resolve) = &resolve {
+                let (width, height) = (width as GLint, height as GLint);
+                gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, resolve.fbo);
+                #[rustfmt::skip]
+                gl::BlitFramebuffer(
+                    0, 0, width, height,
+                    0, 0, width, height,
+                    gl::COLOR_BUFFER_BIT, gl::NEAREST,
+                );
+                gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, framebuffer.fbo);
+                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, resolve.fbo);
+            }
+            Image::read_framebuffer(width, height)
+        };
+        let path = capture
+            .out_dir
+            .join(format!("frame_{:04}.{}", frame, capture.format));
+        image
+            .save(&path)
+            .unwrap_or_else(|err| panic!("Failed to write {:?}: {}", path, err));
+        println!("Wrote {}", path.display());
+    }
+
+    // GL objects have to go before the context that owns them.
+    tutorial.teardown();
+    framework::check_errors(chapter, "teardown");
+    drop(tutorial);
+    drop(resolve);
+    drop(framebuffer);
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn run(_capture: &Capture, _options: &Options, _chapter: &Chapter) {
+    panic!("Headless mode needs EGL, which is only wired up on Linux");
+}
@@ -0,0 +1,755 @@
+//! glTF 2.0 scenes, from `.gltf` files with their buffers embedded as data
+//! URIs or in files next to them, or from `.glb` files.
+//!
+//! The `gltf` crate parses and validates the document; the buffers are read
+//! here, from local files only. A document that requires an extension the
+//! crate doesn't implement fails validation, rather than loading something
+//! that looks wrong.
+//!
+//! Each glTF mesh becomes a [`MeshData`] with one command per primitive,
+//! read through its accessors whatever their component type or the stride
+//! of their buffer view. The attributes are at the usual locations:
+//! position at 0, the material's base color factor at 1 (times `COLOR_0` if
+//! there is one), then normal at 2 and texture coordinate at 3 if any
+//! primitive has them. The nodes keep their local transforms and hierarchy
+//! in a [`Scene`], which [`Scene::flatten`] bakes into a single mesh.
+extern crate gl;
+
+use super::LoadError;
+use crate::mesh::{AttributeArray, Command, Indices, MeshData, SubMesh, Values};
+
+use ::gltf::buffer::Source;
+use ::gltf::mesh::Semantic;
+use ::gltf::Gltf;
+use base64::Engine;
+use cgmath::{Matrix, Matrix3, Matrix4, SquareMatrix, Vector3, Vector4};
+
+use std::path::Path;
+
+/// One node of the scene graph.
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: String,
+    /// Relative to the parent.
+    pub transform: Matrix4<f32>,
+    /// Index into [`Scene::meshes`].
+    pub mesh: Option<usize>,
+    /// Indices into [`Scene::nodes`].
+    pub children: Vec<usize>,
+}
+
+/// The meshes and node hierarchy of a glTF scene.
+#[derive(Clone, Debug)]
+pub struct Scene {
+    pub meshes: Vec<MeshData>,
+    pub nodes: Vec<Node>,
+    /// The nodes at the top of the scene.
+    pub roots: Vec<usize>,
+}
+
+/// Read the glTF or GLB file `path` and flatten its scene into one mesh.
+pub fn load(path: &Path) -> Result<MeshData, LoadError> {
+    let name = path.display().to_string();
+    let data = load_scene(path)?.flatten(&name)?;
+    data.validate()
+        .map_err(|source| LoadError::Mesh { path: name, source })?;
+    Ok(data)
+}
+
+/// Read the glTF or GLB file `path`, with the buffers it refers to.
+pub fn load_scene(path: &Path) -> Result<Scene, LoadError> {
+    let bytes = std::fs::read(path).map_err(|source| LoadError::Io {
+        path: path.display().to_string(),
+        source,
+    })?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    parse(&bytes, &path.display().to_string(), |file| {
+        let path = directory.join(file);
+        std::fs::read(&path).map_err(|source| LoadError::Io {
+            path: path.display().to_string(),
+            source,
+        })
+    })
+}
+
+/// Parse the glTF or GLB document in `bytes`; `path` is only used in
+/// errors. `files` reads an external buffer, given its path relative to the
+/// document.
+pub fn parse<F>(bytes: &[u8], path: &str, mut files: F) -> Result<Scene, LoadError>
+where
+    F: FnMut(&str) -> Result<Vec<u8>, LoadError>,
+{
+    let invalid = |message: String| LoadError::Validation {
+        path: path.to_string(),
+        errors: vec![message],
+    };
+    let gltf = Gltf::from_slice(bytes).map_err(|err| match err {
+        ::gltf::Error::Deserialize(err) => LoadError::Parse {
+            path: path.to_string(),
+            line: err.line() as u32,
+            message: err.to_string(),
+        },
+        ::gltf::Error::Validation(errors) => LoadError::Validation {
+            path: path.to_string(),
+            errors: errors
+                .iter()
+                .map(|(at, error)| format!("{}: {}", at, error))
+                .collect(),
+        },
+        err => invalid(err.to_string()),
+    })?;
+
+    let mut buffers = Vec::new();
+    for buffer in gltf.buffers() {
+        let data = match buffer.source() {
+            Source::Bin => gltf
+                .blob
+                .clone()
+                .ok_or_else(|| invalid(format!("buffer {} has no BIN chunk", buffer.index())))?,
+            Source::Uri(uri) if uri.starts_with("data:") => data_uri(uri)
+                .map_err(|message| invalid(format!("buffer {}: {}", buffer.index(), message)))?,
+            Source::Uri(uri) if uri.contains("://") => {
+                return Err(invalid(format!(
+                    "buffer {} is at {}, only local files are read",
+                    buffer.index(),
+                    uri
+                )))
+            }
+            Source::Uri(uri) => files(&percent_decode(uri))?,
+        };
+        if data.len() < buffer.length() {
+            return Err(invalid(format!(
+                "buffer {} should have {} bytes, but only {} are there",
+                buffer.index(),
+                buffer.length(),
+                data.len()
+            )));
+        }
+        buffers.push(data);
+    }
+
+    let meshes = gltf
+        .meshes()
+        .map(|mesh| read_mesh(&mesh, &buffers).map_err(invalid))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let nodes = gltf
+        .nodes()
+        .map(|node| Node {
+            name: node
+                .name()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("node {}", node.index())),
+            transform: Matrix4::from(node.transform().matrix()),
+            mesh: node.mesh().map(|mesh| mesh.index()),
+            children: node.children().map(|child| child.index()).collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let roots = match gltf.default_scene().or_else(|| gltf.scenes().next()) {
+        Some(scene) => scene.nodes().map(|node| node.index()).collect(),
+        // Without scenes, every node that isn't a child is shown.
+        None => (0..nodes.len())
+            .filter(|&index| nodes.iter().all(|node| !node.children.contains(&index)))
+            .collect(),
+    };
+
+    Ok(Scene {
+        meshes,
+        nodes,
+        roots,
+    })
+}
+
+impl Scene {
+    /// Every mesh of the scene in one, each moved by the transforms of its
+    /// node and the node's ancestors. Each node with a mesh is a sub-mesh
+    /// named after it; `path` is only used in errors.
+    pub fn flatten(&self, path: &str) -> Result<MeshData, LoadError> {
+        let mut placed = Vec::new();
+        let mut stack: Vec<(usize, Matrix4<f32>, usize)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&root| (root, Matrix4::identity(), 0))
+            .collect();
+        while let Some((index, parent, depth)) = stack.pop() {
+            // A node can't be deeper than there are nodes, unless the
+            // hierarchy has a cycle.
+            if depth > self.nodes.len() {
+                return Err(LoadError::Validation {
+                    path: path.to_string(),
+                    errors: vec![format!("node {} is its own ancestor", index)],
+                });
+            }
+            let node = &self.nodes[index];
+            let world = parent * node.transform;
+            if let Some(mesh) = node.mesh {
+                placed.push((index, mesh, world));
+            }
+            for &child in node.children.iter().rev() {
+                stack.push((child, world, depth + 1));
+            }
+        }
+
+        let mut merged = Merged::default();
+        for (index, mesh, world) in placed {
+            let node = &self.nodes[index];
+            let mut name = node.name.clone();
+            if merged
+                .sub_meshes
+                .iter()
+                .any(|sub_mesh| sub_mesh.name == name)
+            {
+                name = format!("{} ({})", name, index);
+            }
+            merged.push(&name, &self.meshes[mesh], world);
+        }
+        Ok(merged.into_mesh_data())
+    }
+}
+
+/// The vertices, indices and primitive of one glTF primitive.
+struct Primitive {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 4]>,
+    /// Flat, 3 values per vertex.
+    normals: Option<Vec<f32>>,
+    /// Flat, 2 values per vertex.
+    texcoords: Option<Vec<f32>>,
+    indices: Option<Vec<u32>>,
+    mode: u32,
+}
+
+fn read_mesh(mesh: &::gltf::Mesh, buffers: &[Vec<u8>]) -> Result<MeshData, String> {
+    let mut merged = Merged::default();
+    for primitive in mesh.primitives() {
+        let what = format!("mesh {} primitive {}", mesh.index(), primitive.index());
+        let primitive = read_primitive(&primitive, buffers)
+            .map_err(|message| format!("{}: {}", what, message))?;
+        merged.add(primitive);
+    }
+    let name = mesh
+        .name()
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("mesh {}", mesh.index()));
+    merged.sub_meshes.push(SubMesh {
+        name,
+        commands: 0..merged.commands.len(),
+    });
+    Ok(merged.into_mesh_data())
+}
+
+fn read_primitive(primitive: &::gltf::Primitive, buffers: &[Vec<u8>]) -> Result<Primitive, String> {
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+    // The readers return None both for a missing attribute and for one
+    // that doesn't fit in its buffer, so look first which are there.
+    let has = |semantic: Semantic| primitive.get(&semantic).is_some();
+    let unreadable = |what: &str| format!("the {} accessor doesn't fit in its buffer", what);
+
+    if !has(Semantic::Positions) {
+        return Err("it has no POSITION attribute".to_string());
+    }
+    let positions: Vec<[f32; 3]> = reader
+        .read_positions()
+        .ok_or_else(|| unreadable("POSITION"))?
+        .collect();
+
+    let base_color = primitive
+        .material()
+        .pbr_metallic_roughness()
+        .base_color_factor();
+    let colors = if has(Semantic::Colors(0)) {
+        reader
+            .read_colors(0)
+            .ok_or_else(|| unreadable("COLOR_0"))?
+            .into_rgba_f32()
+            .map(|color| {
+                [
+                    color[0] * base_color[0],
+                    color[1] * base_color[1],
+                    color[2] * base_color[2],
+                    color[3] * base_color[3],
+                ]
+            })
+            .collect()
+    } else {
+        vec![base_color; positions.len()]
+    };
+
+    let normals = if has(Semantic::Normals) {
+        Some(
+            reader
+                .read_normals()
+                .ok_or_else(|| unreadable("NORMAL"))?
+                .flatten()
+                .collect::<Vec<_>>(),
+        )
+    } else {
+        None
+    };
+    let texcoords = if has(Semantic::TexCoords(0)) {
+        Some(
+            reader
+                .read_tex_coords(0)
+                .ok_or_else(|| unreadable("TEXCOORD_0"))?
+                .into_f32()
+                .flatten()
+                .collect::<Vec<_>>(),
+        )
+    } else {
+        None
+    };
+    let indices = match primitive.indices() {
+        Some(_) => Some(
+            reader
+                .read_indices()
+                .ok_or_else(|| unreadable("index"))?
+                .into_u32()
+                .collect::<Vec<_>>(),
+        ),
+        None => None,
+    };
+
+    let counts = [
+        Some(colors.len()),
+        normals.as_ref().map(|normals| normals.len() / 3),
+        texcoords.as_ref().map(|texcoords| texcoords.len() / 2),
+    ];
+    if counts
+        .iter()
+        .flatten()
+        .any(|&count| count != positions.len())
+    {
+        return Err(format!(
+            "its attributes don't all have {} values",
+            positions.len()
+        ));
+    }
+
+    Ok(Primitive {
+        positions,
+        colors,
+        normals,
+        texcoords,
+        indices,
+        mode: primitive.mode().as_gl_enum(),
+    })
+}
+
+/// Primitives or meshes appended into one set of attributes, with the
+/// indices of each moved past the vertices before it.
+#[derive(Default)]
+struct Merged {
+    positions: Vec<f32>,
+    colors: Vec<f32>,
+    /// `None` until some primitive has normals; vertices without get zeros.
+    normals: Option<Vec<f32>>,
+    texcoords: Option<Vec<f32>>,
+    commands: Vec<(u32, Option<Vec<u32>>, usize, usize)>,
+    sub_meshes: Vec<SubMesh>,
+}
+
+impl Merged {
+    fn vertex_count(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    fn add(&mut self, primitive: Primitive) {
+        let first = self.vertex_count();
+        let count = primitive.positions.len();
+        self.positions
+            .extend(primitive.positions.iter().flatten().copied());
+        self.colors
+            .extend(primitive.colors.iter().flatten().copied());
+        extend(
+            &mut self.normals,
+            primitive.normals.as_deref(),
+            3,
+            first,
+            count,
+        );
+        extend(
+            &mut self.texcoords,
+            primitive.texcoords.as_deref(),
+            2,
+            first,
+            count,
+        );
+        let indices = primitive
+            .indices
+            .map(|indices| indices.iter().map(|&index| index + first as u32).collect());
+        self.commands.push((primitive.mode, indices, first, count));
+    }
+
+    /// Append every command of `mesh`, transformed by `world`, as the
+    /// sub-mesh `name`.
+    fn push(&mut self, name: &str, mesh: &MeshData, world: Matrix4<f32>) {
+        let vertices = mesh.attributes[0].values.len() / 3;
+        let floats = |location: u32| {
+            mesh.attributes
+                .iter()
+                .find(|attribute| attribute.location == location)
+                .map(|attribute| match &attribute.values {
+                    Values::F32(values) => values.as_slice(),
+                    _ => unreachable!("glTF attributes are read as floats"),
+                })
+        };
+
+        let first = self.vertex_count();
+        for position in floats(0).unwrap().chunks(3) {
+            let moved = world * Vector4::new(position[0], position[1], position[2], 1.0);
+            self.positions.extend_from_slice(&[
+                moved.x / moved.w,
+                moved.y / moved.w,
+                moved.z / moved.w,
+            ]);
+        }
+        self.colors.extend_from_slice(floats(1).unwrap());
+
+        // Normals go through the inverse transpose, so they stay
+        // perpendicular under non-uniform scales.
+        let linear = Matrix3::from_cols(world.x.truncate(), world.y.truncate(), world.z.truncate());
+        let normal_matrix = linear.invert().unwrap_or(linear).transpose();
+        let normals = floats(2).map(|normals| {
+            normals
+                .chunks(3)
+                .flat_map(|normal| {
+                    let moved = normal_matrix * Vector3::new(normal[0], normal[1], normal[2]);
+                    let length = (moved.x * moved.x + moved.y * moved.y + moved.z * moved.z).sqrt();
+                    let moved = if length > 0.0 { moved / length } else { moved };
+                    vec![moved.x, moved.y, moved.z]
+                })
+                .collect::<Vec<_>>()
+        });
+        extend(&mut self.normals, normals.as_deref(), 3, first, vertices);
+        extend(&mut self.texcoords, floats(3), 2, first, vertices);
+
+        let start = self.commands.len();
+        for command in &mesh.commands {
+            match command {
+                Command::Elements {
+                    primitive, indices, ..
+                } => self.commands.push((
+                    *primitive,
+                    Some(indices.iter().map(|index| index + first as u32).collect()),
+                    first,
+                    vertices,
+                )),
+                Command::Arrays {
+                    primitive,
+                    first: start,
+                    count,
+                } => self
+                    .commands
+                    .push((*primitive, None, first + start, *count)),
+            }
+        }
+        self.sub_meshes.push(SubMesh {
+            name: name.to_string(),
+            commands: start..self.commands.len(),
+        });
+    }
+
+    fn into_mesh_data(self) -> MeshData {
+        let mut attributes = vec![
+            AttributeArray::new(0, 3, self.positions),
+            AttributeArray::new(1, 4, self.colors),
+        ];
+        if let Some(normals) = self.normals {
+            attributes.push(AttributeArray::new(2, 3, normals));
+        }
+        if let Some(texcoords) = self.texcoords {
+            attributes.push(AttributeArray::new(3, 2, texcoords));
+        }
+
+        let wide = attributes[0].values.len() / 3 > usize::from(u16::MAX);
+        let commands = self
+            .commands
+            .into_iter()
+            .map(|(primitive, indices, first, count)| match indices {
+                Some(indices) if wide => Command::Elements {
+                    primitive,
+                    indices: Indices::U32(indices),
+                    restart: None,
+                },
+                Some(indices) => Command::Elements {
+                    primitive,
+                    indices: Indices::U16(indices.into_iter().map(|index| index as u16).collect()),
+                    restart: None,
+                },
+                None => Command::Arrays {
+                    primitive,
+                    first,
+                    count,
+                },
+            })
+            .collect();
+
+        MeshData {
+            attributes,
+            vertex_arrays: Vec::new(),
+            commands,
+            sub_meshes: self.sub_meshes,
+        }
+    }
+}
+
+/// Append `count` vertices' worth of an optional attribute of `components`
+/// values to `values`, which holds `first` vertices already. Whichever side
+/// is missing is filled with zeros.
+fn extend(
+    values: &mut Option<Vec<f32>>,
+    new: Option<&[f32]>,
+    components: usize,
+    first: usize,
+    count: usize,
+) {
+    if new.is_some() && values.is_none() {
+        *values = Some(vec![0.0; first * components]);
+    }
+    if let Some(values) = values {
+        match new {
+            Some(new) => values.extend_from_slice(new),
+            None => values.resize(values.len() + count * components, 0.0),
+        }
+    }
+}
+
+/// The bytes of a base64 `data:` URI.
+fn data_uri(uri: &str) -> Result<Vec<u8>, String> {
+    let (header, data) = uri
+        .split_once(',')
+        .ok_or_else(|| "the data URI has no ','".to_string())?;
+    if !header.ends_with(";base64") {
+        return Err("only base64 data URIs are supported".to_string());
+    }
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|err| format!("the data URI isn't valid base64: {}", err))
+}
+
+/// Undo the `%20` style escapes of a relative URI.
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 3).and_then(|hex| {
+            std::str::from_utf8(hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        });
+        match (bytes[i], escape) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::STANDARD;
+
+    /// A triangle with interleaved positions and normals, 24 bytes per
+    /// vertex, followed by 3 unsigned byte indices.
+    fn triangle_buffer() -> Vec<u8> {
+        let vertices: [[f32; 6]; 3] = [
+            [0.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+            [0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+        ];
+        let mut bytes: Vec<u8> = vertices
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        bytes.extend_from_slice(&[0, 2, 1]);
+        bytes
+    }
+
+    /// A document drawing the triangle under two nodes, with its buffer at
+    /// `uri`.
+    fn document(uri: &str, extra: &str) -> String {
+        format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                {extra}
+                "buffers": [{{"uri": "{uri}", "byteLength": 75}}],
+                "bufferViews": [
+                    {{"buffer": 0, "byteOffset": 0, "byteLength": 72, "byteStride": 24}},
+                    {{"buffer": 0, "byteOffset": 72, "byteLength": 3}}
+                ],
+                "accessors": [
+                    {{"bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 3,
+                      "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]}},
+                    {{"bufferView": 0, "byteOffset": 12, "componentType": 5126, "count": 3,
+                      "type": "VEC3"}},
+                    {{"bufferView": 1, "componentType": 5121, "count": 3, "type": "SCALAR"}}
+                ],
+                "materials": [
+                    {{"pbrMetallicRoughness": {{"baseColorFactor": [1, 0.5, 0, 1]}}}}
+                ],
+                "meshes": [{{"name": "triangle", "primitives": [
+                    {{"attributes": {{"POSITION": 0, "NORMAL": 1}}, "indices": 2, "material": 0}}
+                ]}}],
+                "nodes": [
+                    {{"name": "parent", "translation": [0, 0, -2], "children": [1]}},
+                    {{"name": "child", "scale": [2, 1, 1], "mesh": 0}}
+                ],
+                "scenes": [{{"nodes": [0]}}],
+                "scene": 0
+            }}"#,
+            uri = uri,
+            extra = extra
+        )
+    }
+
+    fn embedded(extra: &str) -> String {
+        let uri = format!(
+            "data:application/octet-stream;base64,{}",
+            STANDARD.encode(triangle_buffer())
+        );
+        document(&uri, extra)
+    }
+
+    fn no_files(file: &str) -> Result<Vec<u8>, LoadError> {
+        panic!("unexpected read of {}", file)
+    }
+
+    fn errors(result: Result<Scene, LoadError>) -> Vec<String> {
+        match result {
+            Err(LoadError::Validation { errors, .. }) => errors,
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    fn floats(data: &MeshData, location: u32) -> Vec<f32> {
+        match &data
+            .attributes
+            .iter()
+            .find(|a| a.location == location)
+            .unwrap()
+            .values
+        {
+            Values::F32(values) => values.clone(),
+            other => panic!("unexpected values {:?}", other),
+        }
+    }
+
+    #[test]
+    fn interleaved_embedded_buffer() {
+        let scene = parse(embedded("").as_bytes(), "test.gltf", no_files).unwrap();
+        assert_eq!(scene.roots, vec![0]);
+        assert_eq!(scene.nodes[0].children, vec![1]);
+        assert_eq!(scene.nodes[1].mesh, Some(0));
+        assert_eq!(scene.meshes[0].validate(), Ok(3));
+        assert_eq!(scene.meshes[0].sub_meshes[0].name, "triangle");
+
+        let data = scene.flatten("test.gltf").unwrap();
+        assert_eq!(data.validate(), Ok(3));
+        assert_eq!(
+            floats(&data, 0),
+            vec![0.0, 0.0, -2.0, 2.0, 0.0, -2.0, 0.0, 1.0, -2.0]
+        );
+        assert_eq!(floats(&data, 1), [1.0, 0.5, 0.0, 1.0].repeat(3));
+        assert_eq!(floats(&data, 2), [0.0, 0.0, 1.0].repeat(3));
+        match &data.commands[..] {
+            [Command::Elements {
+                primitive,
+                indices: Indices::U16(indices),
+                restart: None,
+            }] => {
+                assert_eq!(*primitive, gl::TRIANGLES);
+                assert_eq!(indices, &vec![0, 2, 1]);
+            }
+            other => panic!("unexpected commands {:?}", other),
+        }
+        assert_eq!(data.sub_meshes.len(), 1);
+        assert_eq!(data.sub_meshes[0].name, "child");
+    }
+
+    #[test]
+    fn external_buffer() {
+        let text = document("tri%20angle.bin", "");
+        let mut read = Vec::new();
+        let scene = parse(text.as_bytes(), "test.gltf", |file| {
+            read.push(file.to_string());
+            Ok(triangle_buffer())
+        })
+        .unwrap();
+        assert_eq!(read, vec!["tri angle.bin"]);
+        assert_eq!(scene.flatten("test.gltf").unwrap().validate(), Ok(3));
+    }
+
+    #[test]
+    fn binary_gltf() {
+        let text = document("", "").replace(r#""uri": "", "#, "");
+        let mut json = text.into_bytes();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut bin = triangle_buffer();
+        while !bin.len().is_multiple_of(4) {
+            bin.push(0);
+        }
+
+        let mut glb = Vec::new();
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"JSON");
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"BIN\0");
+        glb.extend_from_slice(&bin);
+
+        let scene = parse(&glb, "test.glb", no_files).unwrap();
+        assert_eq!(scene.flatten("test.glb").unwrap().validate(), Ok(3));
+    }
+
+    #[test]
+    fn unsupported_extension() {
+        let text = embedded(r#""extensionsRequired": ["KHR_draco_mesh_compression"],"#);
+        assert_eq!(
+            errors(parse(text.as_bytes(), "test.gltf", no_files)),
+            vec!["extensionsRequired[0] = \"KHR_draco_mesh_compression\": Unsupported extension"]
+        );
+    }
+
+    #[test]
+    fn remote_buffer() {
+        let text = document("https://example.com/triangle.bin", "");
+        assert_eq!(
+            errors(parse(text.as_bytes(), "test.gltf", no_files)),
+            vec!["buffer 0 is at https://example.com/triangle.bin, only local files are read"]
+        );
+    }
+
+    #[test]
+    fn short_buffer() {
+        let text = document("triangle.bin", "");
+        let result = parse(text.as_bytes(), "test.gltf", |_| Ok(vec![0; 40]));
+        assert_eq!(
+            errors(result),
+            vec!["buffer 0 should have 75 bytes, but only 40 are there"]
+        );
+    }
+
+    #[test]
+    fn not_json() {
+        match parse(b"{\n  \"asset\": ", "test.gltf", no_files) {
+            Err(LoadError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Reading meshes from files.
+//!
+//! Every format is parsed into a [`MeshData`], without touching GL, and
+//! checked with [`MeshData::validate`] before it's returned.
+pub mod gltf;
+pub mod obj;
+pub mod xml;
+
+use crate::mesh::{Mesh, MeshData, MeshError};
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read.
+    Io { path: String, source: io::Error },
+    /// The file isn't well-formed, or not what the format allows.
+    Parse {
+        path: String,
+        line: u32,
+        message: String,
+    },
+    /// The file parsed, but breaks rules of its format that aren't tied to
+    /// a line, like requiring an unsupported extension.
+    Validation { path: String, errors: Vec<String> },
+    /// The file parsed, but what it describes doesn't fit together.
+    Mesh { path: String, source: MeshError },
+    /// No loader for this kind of file.
+    UnknownFormat { path: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io { path, source } => write!(f, "Failed to read {}: {}", path, source),
+            LoadError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "Failed to parse {}:{}: {}", path, line, message),
+            LoadError::Validation { path, errors } => {
+                write!(f, "Failed to load {}: {}", path, errors.join("; "))
+            }
+            LoadError::Mesh { path, source } => write!(f, "Failed to load {}: {}", path, source),
+            LoadError::UnknownFormat { path } => {
+                write!(f, "Failed to load {}: unknown mesh format", path)
+            }
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io { source, .. } => Some(source),
+            LoadError::Mesh { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Read the mesh in `path`, in the format its extension names.
+pub fn load(path: &Path) -> Result<MeshData, LoadError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("gltf") | Some("glb") => gltf::load(path),
+        Some("obj") => obj::load(path),
+        Some("xml") => xml::load(path),
+        _ => Err(LoadError::UnknownFormat {
+            path: path.display().to_string(),
+        }),
+    }
+}
+
+/// Read the mesh in `path` and upload it.
+pub fn load_mesh(path: &Path) -> Result<Mesh, LoadError> {
+    let data = load(path)?;
+    Mesh::new(&data).map_err(|source| LoadError::Mesh {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+fn read(path: &Path) -> Result<String, LoadError> {
+    std::fs::read_to_string(path).map_err(|source| LoadError::Io {
+        path: path.display().to_string(),
+        source,
+    })
+}
@@ -0,0 +1,465 @@
+//! Wavefront OBJ meshes, with the diffuse colors of their MTL materials.
+//!
+//! Positions, texture coordinates and normals are read from `v`, `vt` and
+//! `vn`, and `f` faces of any size are split into triangle fans. Each
+//! distinct combination of position, texture coordinate, normal and
+//! material becomes one vertex, so the result draws with `glDrawElements`
+//! like the tutorial meshes. Every `o` object or `g` group becomes a
+//! sub-mesh of one command; faces before the first of them go into
+//! "default".
+//!
+//! The attributes are at the same locations as everywhere else: position at
+//! 0, the material's `Kd` color at 1 (white without one), then normal at 2
+//! and texture coordinate at 3 if the file has them. Other statements, like
+//! lines or smoothing groups, are skipped. A material library that can't be
+//! read, or a material none of them define, only costs the color: it's
+//! reported as a warning and drawn white.
+extern crate gl;
+
+use super::{read, LoadError};
+use crate::mesh::{AttributeArray, Command, Indices, MeshData, SubMesh};
+
+use std::collections::HashMap;
+use std::path::Path;
+
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Read the OBJ file `path`, and the material libraries next to it.
+pub fn load(path: &Path) -> Result<MeshData, LoadError> {
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    parse(&read(path)?, &path.display().to_string(), |library| {
+        let path = directory.join(library);
+        Ok((read(&path)?, path.display().to_string()))
+    })
+}
+
+/// Parse the OBJ file in `text`; `path` is only used in errors.
+/// `materials` reads the named material library and returns its text and
+/// path.
+pub fn parse<F>(text: &str, path: &str, mut materials: F) -> Result<MeshData, LoadError>
+where
+    F: FnMut(&str) -> Result<(String, String), LoadError>,
+{
+    let mut obj = Obj::default();
+    let mut colors: HashMap<String, [f32; 4]> = HashMap::new();
+    let mut material = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let error = |message: String| LoadError::Parse {
+            path: path.to_string(),
+            line: number as u32 + 1,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let words: Vec<&str> = words.collect();
+        match keyword {
+            "v" => obj.positions.push(floats(&words, 3, 3).map_err(error)?),
+            "vt" => {
+                let uv = floats(&words, 1, 2).map_err(error)?;
+                obj.texcoords
+                    .push([uv[0], uv.get(1).cloned().unwrap_or(0.0)]);
+            }
+            "vn" => obj.normals.push(floats(&words, 3, 3).map_err(error)?),
+            "f" => {
+                if words.len() < 3 {
+                    return Err(error(format!(
+                        "a face needs at least 3 vertices, this one has {}",
+                        words.len()
+                    )));
+                }
+                let corners = words
+                    .iter()
+                    .map(|corner| obj.corner(corner, material))
+                    .collect::<Result<Vec<u32>, String>>()
+                    .map_err(error)?;
+                let indices = obj.group_indices();
+                for i in 1..corners.len() - 1 {
+                    indices.extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            "o" | "g" => obj.set_group(&words.join(" ")),
+            "mtllib" => {
+                for library in &words {
+                    let loaded =
+                        materials(library).and_then(|(text, path)| parse_materials(&text, &path));
+                    match loaded {
+                        Ok(library) => colors.extend(library),
+                        Err(err) => eprintln!("Warning: {}, drawing its materials white", err),
+                    }
+                }
+            }
+            "usemtl" => {
+                let name = words.join(" ");
+                material = match colors.get(&name) {
+                    Some(&color) => Some(obj.material(&name, color)),
+                    None => {
+                        eprintln!(
+                            "Warning: {}:{}: unknown material \"{}\", drawing it white",
+                            path,
+                            number + 1,
+                            name
+                        );
+                        None
+                    }
+                };
+            }
+            _ => {}
+        }
+    }
+
+    let data = obj.into_mesh_data();
+    data.validate().map_err(|source| LoadError::Mesh {
+        path: path.to_string(),
+        source,
+    })?;
+    Ok(data)
+}
+
+/// The `Kd` colors of the materials in an MTL file.
+fn parse_materials(text: &str, path: &str) -> Result<HashMap<String, [f32; 4]>, LoadError> {
+    let mut colors = HashMap::new();
+    let mut current = None;
+    for (number, line) in text.lines().enumerate() {
+        let error = |message: String| LoadError::Parse {
+            path: path.to_string(),
+            line: number as u32 + 1,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let words: Vec<&str> = words.collect();
+        match keyword {
+            "newmtl" => {
+                let name = words.join(" ");
+                colors.insert(name.clone(), WHITE);
+                current = Some(name);
+            }
+            "Kd" => {
+                let name = current
+                    .as_ref()
+                    .ok_or_else(|| error("Kd before the first newmtl".to_string()))?;
+                let rgb = floats(&words, 3, 3).map_err(error)?;
+                colors.insert(name.clone(), [rgb[0], rgb[1], rgb[2], 1.0]);
+            }
+            _ => {}
+        }
+    }
+    Ok(colors)
+}
+
+/// Between `min` and `max` numbers from the start of `words`; anything after
+/// `max` is ignored, such as the `w` of a position.
+fn floats(words: &[&str], min: usize, max: usize) -> Result<Vec<f32>, String> {
+    if words.len() < min {
+        return Err(format!(
+            "expected at least {} numbers, got {}",
+            min,
+            words.len()
+        ));
+    }
+    words
+        .iter()
+        .take(max)
+        .map(|word| {
+            word.parse()
+                .map_err(|_| format!("\"{}\" isn't a number", word))
+        })
+        .collect()
+}
+
+/// The index that the 1-based, or negative for counting back from the end,
+/// OBJ index `word` refers to among `count` elements.
+fn resolve(word: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let index: i64 = word
+        .parse()
+        .map_err(|_| format!("\"{}\" isn't a {} index", word, kind))?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "{} index {} is out of range, there are {}",
+            kind, index, count
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// A corner of a face: indices of the position, texture coordinate, normal
+/// and material.
+type Key = (usize, Option<usize>, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct Obj {
+    positions: Vec<Vec<f32>>,
+    texcoords: Vec<[f32; 2]>,
+    normals: Vec<Vec<f32>>,
+    materials: Vec<(String, [f32; 4])>,
+    /// Each distinct corner, in the order they were first used.
+    vertices: Vec<Key>,
+    lookup: HashMap<Key, u32>,
+    groups: Vec<(String, Vec<u32>)>,
+    group: Option<usize>,
+}
+
+impl Obj {
+    /// The vertex for a face corner such as `3/1/2`, `3//2` or `-1`.
+    fn corner(&mut self, corner: &str, material: Option<usize>) -> Result<u32, String> {
+        let mut parts = corner.split('/');
+        let position = resolve(parts.next().unwrap_or(""), self.positions.len(), "position")?;
+        let texcoord = match parts.next() {
+            Some("") | None => None,
+            Some(word) => Some(resolve(word, self.texcoords.len(), "texture coordinate")?),
+        };
+        let normal = match parts.next() {
+            Some("") | None => None,
+            Some(word) => Some(resolve(word, self.normals.len(), "normal")?),
+        };
+
+        let key = (position, texcoord, normal, material);
+        let next = self.vertices.len() as u32;
+        let index = *self.lookup.entry(key).or_insert(next);
+        if index == next {
+            self.vertices.push(key);
+        }
+        Ok(index)
+    }
+
+    fn material(&mut self, name: &str, color: [f32; 4]) -> usize {
+        match self.materials.iter().position(|(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                self.materials.push((name.to_string(), color));
+                self.materials.len() - 1
+            }
+        }
+    }
+
+    /// Send the following faces to the group called `name`, picking up where
+    /// it left off if there was one already.
+    fn set_group(&mut self, name: &str) {
+        let name = if name.is_empty() { "default" } else { name };
+        self.group = match self.groups.iter().position(|(n, _)| n == name) {
+            Some(index) => Some(index),
+            None => {
+                self.groups.push((name.to_string(), Vec::new()));
+                Some(self.groups.len() - 1)
+            }
+        };
+    }
+
+    fn group_indices(&mut self) -> &mut Vec<u32> {
+        if self.group.is_none() {
+            self.set_group("default");
+        }
+        let group = self.group.unwrap();
+        &mut self.groups[group].1
+    }
+
+    fn into_mesh_data(self) -> MeshData {
+        let mut positions = Vec::with_capacity(self.vertices.len() * 3);
+        let mut colors = Vec::with_capacity(self.vertices.len() * 4);
+        let mut normals = Vec::new();
+        let mut texcoords = Vec::new();
+        let has_normals = self.vertices.iter().any(|key| key.2.is_some());
+        let has_texcoords = self.vertices.iter().any(|key| key.1.is_some());
+
+        for &(position, texcoord, normal, material) in &self.vertices {
+            positions.extend_from_slice(&self.positions[position][..3]);
+            colors.extend_from_slice(&material.map_or(WHITE, |m| self.materials[m].1));
+            if has_normals {
+                normals.extend_from_slice(normal.map_or(&[0.0; 3][..], |n| &self.normals[n][..3]));
+            }
+            if has_texcoords {
+                texcoords.extend_from_slice(&texcoord.map_or([0.0; 2], |t| self.texcoords[t]));
+            }
+        }
+
+        let mut attributes = vec![
+            AttributeArray::new(0, 3, positions),
+            AttributeArray::new(1, 4, colors),
+        ];
+        if has_normals {
+            attributes.push(AttributeArray::new(2, 3, normals));
+        }
+        if has_texcoords {
+            attributes.push(AttributeArray::new(3, 2, texcoords));
+        }
+
+        let wide = self.vertices.len() > usize::from(u16::MAX);
+        let mut commands = Vec::new();
+        let mut sub_meshes = Vec::new();
+        for (name, indices) in self.groups {
+            if indices.is_empty() {
+                continue;
+            }
+            let indices = if wide {
+                Indices::U32(indices)
+            } else {
+                Indices::U16(indices.into_iter().map(|index| index as u16).collect())
+            };
+            sub_meshes.push(SubMesh {
+                name,
+                commands: commands.len()..commands.len() + 1,
+            });
+            commands.push(Command::Elements {
+                primitive: gl::TRIANGLES,
+                indices,
+                restart: None,
+            });
+        }
+
+        MeshData {
+            attributes,
+            vertex_arrays: Vec::new(),
+            commands,
+            sub_meshes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::Values;
+
+    use std::io;
+
+    const MATERIALS: &str = "newmtl red\nKd 1 0 0\nnewmtl plain\n";
+
+    fn obj(text: &str) -> Result<MeshData, LoadError> {
+        parse(text, "test.obj", |library| {
+            assert_eq!(library, "test.mtl");
+            Ok((MATERIALS.to_string(), "test.mtl".to_string()))
+        })
+    }
+
+    fn parse_error(text: &str) -> (u32, String) {
+        match obj(text) {
+            Err(LoadError::Parse { line, message, .. }) => (line, message),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    fn indices(command: &Command) -> Vec<u32> {
+        match command {
+            Command::Elements { indices, .. } => indices.iter().collect(),
+            other => panic!("unexpected command {:?}", other),
+        }
+    }
+
+    #[test]
+    fn quad_is_a_triangle_fan() {
+        let data = obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+        assert_eq!(data.validate(), Ok(4));
+        assert_eq!(data.attributes.len(), 2);
+        assert_eq!(indices(&data.commands[0]), vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(data.sub_meshes.len(), 1);
+        assert_eq!(data.sub_meshes[0].name, "default");
+        assert_eq!(data.attributes[1].values, Values::F32(WHITE.repeat(4)));
+    }
+
+    #[test]
+    fn negative_indices_and_dedup() {
+        let data = obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nvn 0 0 1\nvt 0.5\n\
+             f -3//-1 -2//-1 -1//-1\nf 1//1 3//1 2//1\nf 1/1/1 2//1 3//1\n")
+        .unwrap();
+        // The last face's first corner has a texture coordinate, which makes
+        // it a vertex of its own.
+        assert_eq!(data.validate(), Ok(4));
+        assert_eq!(indices(&data.commands[0]), vec![0, 1, 2, 0, 2, 1, 3, 1, 2]);
+        assert_eq!(data.attributes[2].location, 2);
+        assert_eq!(data.attributes[3].location, 3);
+        assert_eq!(
+            data.attributes[3].values,
+            Values::F32(vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0])
+        );
+    }
+
+    #[test]
+    fn groups_and_materials() {
+        let data = obj("mtllib test.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\n\
+             o first\nusemtl red\nf 1 2 3\n\
+             g second\nusemtl plain\nf 1 2 3\n\
+             o first\nf 3 2 1\n")
+        .unwrap();
+        assert_eq!(data.validate(), Ok(6));
+        let names: Vec<&str> = data.sub_meshes.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second"]);
+        assert_eq!(data.commands.len(), 2);
+        assert_eq!(indices(&data.commands[0]), vec![0, 1, 2, 5, 4, 3]);
+        assert_eq!(indices(&data.commands[1]), vec![3, 4, 5]);
+        match &data.attributes[1].values {
+            Values::F32(colors) => {
+                assert_eq!(&colors[..4], &[1.0, 0.0, 0.0, 1.0]);
+                assert_eq!(&colors[12..16], &WHITE);
+            }
+            other => panic!("unexpected colors {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_faces() {
+        assert_eq!(
+            parse_error("v 0 0 0\nf 1 1\n"),
+            (
+                2,
+                "a face needs at least 3 vertices, this one has 2".to_string()
+            )
+        );
+        assert_eq!(
+            parse_error("v 0 0 0\n\nf 1 2 1\n"),
+            (
+                3,
+                "position index 2 is out of range, there are 1".to_string()
+            )
+        );
+        assert_eq!(
+            parse_error("v 0 0 0\nf 1 0 -2\n"),
+            (
+                2,
+                "position index 0 is out of range, there are 1".to_string()
+            )
+        );
+        assert_eq!(
+            parse_error("v 0 0 zero\n"),
+            (1, "\"zero\" isn't a number".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_materials() {
+        let text = "mtllib gone.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nusemtl red\nf 1 2 3\n";
+        let data = parse(text, "test.obj", |library| {
+            Err(LoadError::Io {
+                path: library.to_string(),
+                source: io::Error::new(io::ErrorKind::NotFound, "not found"),
+            })
+        })
+        .unwrap();
+        assert_eq!(data.attributes[1].values, Values::F32(WHITE.repeat(3)));
+
+        let data =
+            obj("mtllib test.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nusemtl blue\nf 1 2 3\n").unwrap();
+        assert_eq!(data.attributes[1].values, Values::F32(WHITE.repeat(3)));
+    }
+
+    #[test]
+    fn no_faces() {
+        match obj("v 0 0 0\n") {
+            Err(LoadError::Mesh { .. }) => {}
+            other => panic!("expected a mesh error, got {:?}", other),
+        }
+    }
+}
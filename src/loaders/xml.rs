@@ -0,0 +1,451 @@
+//! The XML mesh format of the gltut tutorials, e.g. `UnitCubeColor.xml`:
+//!
+//! ```xml
+//! <mesh xmlns="http://www.arcsynthesis.com/gltut/mesh">
+//!     <attribute index="0" type="float" size="3">
+//!         0.5 0.5 0.5  ...
+//!     </attribute>
+//!     <vao name="flat">
+//!         <source attrib="0"/>
+//!     </vao>
+//!     <indices cmd="triangles" type="ushort">0 1 2  ...</indices>
+//!     <arrays cmd="lines" start="0" count="8"/>
+//! </mesh>
+//! ```
+//!
+//! Commands are drawn in the order they appear. `indices` take an optional
+//! `prim-restart` index.
+extern crate gl;
+use self::gl::types::*;
+
+use super::{read, LoadError};
+use crate::mesh::{AttributeArray, Command, Indices, MeshData, NamedVertexArray, Values};
+use crate::vertex::ComponentType;
+
+use roxmltree::{Document, Node};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The `type`s of `attribute`: how the values are stored, and whether
+/// they are normalized.
+const ATTRIBUTE_TYPES: &[(&str, ComponentType, bool)] = &[
+    ("float", ComponentType::F32, false),
+    ("int", ComponentType::I32, false),
+    ("uint", ComponentType::U32, false),
+    ("norm-int", ComponentType::I32, true),
+    ("norm-uint", ComponentType::U32, true),
+    ("short", ComponentType::I16, false),
+    ("ushort", ComponentType::U16, false),
+    ("norm-short", ComponentType::I16, true),
+    ("norm-ushort", ComponentType::U16, true),
+    ("byte", ComponentType::I8, false),
+    ("ubyte", ComponentType::U8, false),
+    ("norm-byte", ComponentType::I8, true),
+    ("norm-ubyte", ComponentType::U8, true),
+];
+
+const PRIMITIVES: &[(&str, GLenum)] = &[
+    ("triangles", gl::TRIANGLES),
+    ("tri-strip", gl::TRIANGLE_STRIP),
+    ("tri-fan", gl::TRIANGLE_FAN),
+    ("lines", gl::LINES),
+    ("line-strip", gl::LINE_STRIP),
+    ("line-loop", gl::LINE_LOOP),
+    ("points", gl::POINTS),
+];
+
+/// Read the mesh in the XML file `path`.
+pub fn load(path: &Path) -> Result<MeshData, LoadError> {
+    parse(&read(path)?, &path.display().to_string())
+}
+
+/// Parse the mesh in `text`; `path` is only used in errors.
+pub fn parse(text: &str, path: &str) -> Result<MeshData, LoadError> {
+    let document = Document::parse(text).map_err(|err| LoadError::Parse {
+        path: path.to_string(),
+        line: err.pos().row,
+        message: err.to_string(),
+    })?;
+    let parser = Parser {
+        document: &document,
+        path,
+    };
+
+    let root = document.root_element();
+    if root.tag_name().name() != "mesh" {
+        return Err(parser.error(root, "the root element has to be <mesh>"));
+    }
+
+    let mut mesh = MeshData::default();
+    for node in root.children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "attribute" => mesh.attributes.push(parser.attribute(node)?),
+            "vao" => mesh.vertex_arrays.push(parser.vertex_array(node)?),
+            "indices" => mesh.commands.push(parser.indices(node)?),
+            "arrays" => mesh.commands.push(parser.arrays(node)?),
+            other => return Err(parser.error(node, &format!("unknown element <{}>", other))),
+        }
+    }
+
+    mesh.validate().map_err(|source| LoadError::Mesh {
+        path: path.to_string(),
+        source,
+    })?;
+    Ok(mesh)
+}
+
+struct Parser<'a, 'input> {
+    document: &'a Document<'input>,
+    path: &'a str,
+}
+
+impl Parser<'_, '_> {
+    fn error(&self, node: Node, message: &str) -> LoadError {
+        LoadError::Parse {
+            path: self.path.to_string(),
+            line: self.document.text_pos_at(node.range().start).row,
+            message: message.to_string(),
+        }
+    }
+
+    fn required<'n>(&self, node: Node<'n, '_>, name: &str) -> Result<&'n str, LoadError> {
+        node.attribute(name).ok_or_else(|| {
+            self.error(
+                node,
+                &format!(
+                    "<{}> is missing the {} attribute",
+                    node.tag_name().name(),
+                    name
+                ),
+            )
+        })
+    }
+
+    fn number<T: FromStr>(&self, node: Node, name: &str) -> Result<T, LoadError> {
+        let value = self.required(node, name)?;
+        value.trim().parse().map_err(|_| {
+            self.error(
+                node,
+                &format!("{}=\"{}\" isn't a valid number", name, value),
+            )
+        })
+    }
+
+    fn primitive(&self, node: Node) -> Result<GLenum, LoadError> {
+        let cmd = self.required(node, "cmd")?;
+        PRIMITIVES
+            .iter()
+            .find(|(name, _)| *name == cmd)
+            .map(|(_, primitive)| *primitive)
+            .ok_or_else(|| self.error(node, &format!("unknown cmd \"{}\"", cmd)))
+    }
+
+    /// The whitespace separated numbers in the text of `node`.
+    fn values<T: FromStr>(&self, node: Node, kind: &str) -> Result<Vec<T>, LoadError> {
+        node.text()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    let message = if value.parse::<f64>().is_ok() {
+                        format!("{} doesn't fit in a {}", value, kind)
+                    } else {
+                        format!("\"{}\" isn't a valid {} value", value, kind)
+                    };
+                    self.error(node, &message)
+                })
+            })
+            .collect()
+    }
+
+    fn attribute(&self, node: Node) -> Result<AttributeArray, LoadError> {
+        let location = self.number(node, "index")?;
+        let components = self.number(node, "size")?;
+        let name = self.required(node, "type")?;
+        let (kind, normalized) = match ATTRIBUTE_TYPES.iter().find(|(n, ..)| *n == name) {
+            Some((_, kind, normalized)) => (*kind, *normalized),
+            None if name == "half" => {
+                return Err(self.error(node, "half float attributes aren't supported"))
+            }
+            None => return Err(self.error(node, &format!("unknown attribute type \"{}\"", name))),
+        };
+        let values = match kind {
+            ComponentType::F32 => Values::F32(self.values(node, name)?),
+            ComponentType::I8 => Values::I8(self.values(node, name)?),
+            ComponentType::U8 => Values::U8(self.values(node, name)?),
+            ComponentType::I16 => Values::I16(self.values(node, name)?),
+            ComponentType::U16 => Values::U16(self.values(node, name)?),
+            ComponentType::I32 => Values::I32(self.values(node, name)?),
+            ComponentType::U32 => Values::U32(self.values(node, name)?),
+        };
+        Ok(AttributeArray {
+            location,
+            components,
+            normalized,
+            values,
+        })
+    }
+
+    fn vertex_array(&self, node: Node) -> Result<NamedVertexArray, LoadError> {
+        let name = self.required(node, "name")?.to_string();
+        let mut locations = Vec::new();
+        for source in node.children().filter(Node::is_element) {
+            if source.tag_name().name() != "source" {
+                return Err(self.error(source, "<vao> can only contain <source> elements"));
+            }
+            locations.push(self.number(source, "attrib")?);
+        }
+        Ok(NamedVertexArray {
+            name,
+            locations,
+            first_vertex: 0,
+        })
+    }
+
+    fn indices(&self, node: Node) -> Result<Command, LoadError> {
+        let primitive = self.primitive(node)?;
+        let indices = match self.required(node, "type")? {
+            "ubyte" => Indices::U8(self.values(node, "ubyte")?),
+            "ushort" => Indices::U16(self.values(node, "ushort")?),
+            "uint" => Indices::U32(self.values(node, "uint")?),
+            other => return Err(self.error(node, &format!("unknown index type \"{}\"", other))),
+        };
+        let restart = match node.attribute("prim-restart") {
+            Some(_) => Some(self.number(node, "prim-restart")?),
+            None => None,
+        };
+        Ok(Command::Elements {
+            primitive,
+            indices,
+            restart,
+        })
+    }
+
+    fn arrays(&self, node: Node) -> Result<Command, LoadError> {
+        Ok(Command::Arrays {
+            primitive: self.primitive(node)?,
+            first: self.number(node, "start")?,
+            count: self.number(node, "count")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::MeshError;
+
+    fn mesh(body: &str) -> Result<MeshData, LoadError> {
+        parse(
+            &format!(
+                "<mesh xmlns=\"http://www.arcsynthesis.com/gltut/mesh\">\n{}\n</mesh>",
+                body
+            ),
+            "test.xml",
+        )
+    }
+
+    fn parse_error(body: &str) -> (u32, String) {
+        match mesh(body) {
+            Err(LoadError::Parse { line, message, .. }) => (line, message),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    fn mesh_error(body: &str) -> MeshError {
+        match mesh(body) {
+            Err(LoadError::Mesh { source, .. }) => source,
+            other => panic!("expected a mesh error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unit_cube_color() {
+        let data = load(Path::new("src/meshes/UnitCubeColor.xml")).unwrap();
+        assert_eq!(data.validate(), Ok(24));
+        assert_eq!(data.attributes.len(), 2);
+        assert_eq!(data.attributes[0].location, 0);
+        assert_eq!(data.attributes[0].components, 3);
+        assert_eq!(data.attributes[1].values.len(), 24 * 4);
+        assert_eq!(data.vertex_arrays.len(), 1);
+        assert_eq!(data.vertex_arrays[0].name, "flat");
+        assert_eq!(data.vertex_arrays[0].locations, vec![0]);
+        match &data.commands[..] {
+            [Command::Elements {
+                primitive,
+                indices: Indices::U16(indices),
+                restart: None,
+            }] => {
+                assert_eq!(*primitive, gl::TRIANGLES);
+                assert_eq!(indices.len(), 36);
+            }
+            other => panic!("unexpected commands {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unit_plane() {
+        let data = load(Path::new("src/meshes/UnitPlane.xml")).unwrap();
+        assert_eq!(data.validate(), Ok(4));
+        assert!(data.vertex_arrays.is_empty());
+        assert_eq!(data.commands.len(), 1);
+    }
+
+    #[test]
+    fn unit_cylinder() {
+        let data = load(Path::new("src/meshes/UnitCylinder.xml")).unwrap();
+        assert_eq!(data.validate(), Ok(122));
+        assert_eq!(data.attributes.len(), 3);
+        let names: Vec<_> = data.vertex_arrays.iter().map(|vao| &vao.name[..]).collect();
+        assert_eq!(names, ["lit-color", "lit", "color", "flat"]);
+        assert_eq!(data.vertex_arrays[1].locations, vec![0, 2]);
+        let primitives: Vec<_> = data
+            .commands
+            .iter()
+            .map(|command| match command {
+                Command::Elements { primitive, .. } => *primitive,
+                other => panic!("unexpected command {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            primitives,
+            [gl::TRIANGLE_FAN, gl::TRIANGLE_FAN, gl::TRIANGLE_STRIP]
+        );
+    }
+
+    #[test]
+    fn attribute_types() {
+        let data = mesh(
+            r#"<attribute index="0" type="norm-ubyte" size="4">255 0 0 255 0 255 0 255</attribute>
+            <attribute index="2" type="short" size="2">-1 1 -32768 32767</attribute>
+            <arrays cmd="lines" start="0" count="2"/>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            data.attributes[0].values,
+            Values::U8(vec![255, 0, 0, 255, 0, 255, 0, 255])
+        );
+        assert!(data.attributes[0].normalized);
+        assert_eq!(
+            data.attributes[1].values,
+            Values::I16(vec![-1, 1, -32768, 32767])
+        );
+        assert!(!data.attributes[1].normalized);
+        assert_eq!(
+            data.commands,
+            vec![Command::Arrays {
+                primitive: gl::LINES,
+                first: 0,
+                count: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn primitive_restart() {
+        let data = mesh(
+            r#"<attribute index="0" type="float" size="2">0 0 1 0 1 1 0 1</attribute>
+            <indices cmd="tri-strip" type="ubyte" prim-restart="255">0 1 2 255 1 2 3</indices>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            data.commands,
+            vec![Command::Elements {
+                primitive: gl::TRIANGLE_STRIP,
+                indices: Indices::U8(vec![0, 1, 2, 255, 1, 2, 3]),
+                restart: Some(255),
+            }]
+        );
+    }
+
+    #[test]
+    fn index_overflow() {
+        let (line, message) = parse_error(
+            r#"<attribute index="0" type="float" size="1">0 1 2</attribute>
+            <indices cmd="triangles" type="ushort">0 1 70000</indices>"#,
+        );
+        assert_eq!(line, 3);
+        assert_eq!(message, "70000 doesn't fit in a ushort");
+    }
+
+    #[test]
+    fn index_past_the_vertices() {
+        assert_eq!(
+            mesh_error(
+                r#"<attribute index="0" type="float" size="3">0 0 0 1 0 0 0 1 0</attribute>
+                <indices cmd="triangles" type="ushort">0 1 3</indices>"#
+            ),
+            MeshError::OutOfRange {
+                command: 0,
+                vertex: 3,
+                available: 3
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_attributes() {
+        let (line, message) = parse_error(
+            r#"<attribute index="0" type="float" size="3">0 0 0</attribute>
+            <attribute index="1" type="double" size="3">0 0 0</attribute>"#,
+        );
+        assert_eq!(
+            (line, message.as_str()),
+            (3, "unknown attribute type \"double\"")
+        );
+
+        let (_, message) = parse_error(r#"<attribute index="0" type="float">0 0 0</attribute>"#);
+        assert_eq!(message, "<attribute> is missing the size attribute");
+
+        let (_, message) =
+            parse_error(r#"<attribute index="0" type="float" size="3">0 0 zero</attribute>"#);
+        assert_eq!(message, "\"zero\" isn't a valid float value");
+
+        let (_, message) =
+            parse_error(r#"<attribute index="0" type="ubyte" size="1">0 256</attribute>"#);
+        assert_eq!(message, "256 doesn't fit in a ubyte");
+
+        assert_eq!(
+            mesh_error(r#"<attribute index="0" type="float" size="3">0 0 0 1</attribute>"#),
+            MeshError::PartialVertex {
+                location: 0,
+                values: 4,
+                components: 3
+            }
+        );
+        assert_eq!(
+            mesh_error(
+                r#"<attribute index="0" type="float" size="3">0 0 0 1 1 1</attribute>
+                <attribute index="1" type="float" size="4">1 1 1 1</attribute>"#
+            ),
+            MeshError::VertexCount {
+                location: 1,
+                count: 1,
+                expected: 2
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_vertex_array_source() {
+        assert_eq!(
+            mesh_error(
+                r#"<attribute index="0" type="float" size="1">0</attribute>
+                <vao name="flat"><source attrib="1"/></vao>"#
+            ),
+            MeshError::UnknownLocation {
+                vertex_array: "flat".to_string(),
+                location: 1
+            }
+        );
+    }
+
+    #[test]
+    fn not_xml() {
+        let err = parse("<mesh><attribute></mesh>", "broken.xml").unwrap_err();
+        assert!(matches!(err, LoadError::Parse { line: 1, .. }), "{:?}", err);
+        assert!(err
+            .to_string()
+            .starts_with("Failed to parse broken.xml:1: "));
+    }
+}
@@ -1,27 +1,184 @@
-use glfw::{Action, Context, Key};
+mod capture;
 mod ch_1;
 mod ch_2;
 mod ch_3;
 mod ch_4;
+mod cli;
+mod clock;
+mod debug;
+mod framework;
+mod headless;
+mod loaders;
+mod mesh;
+mod objects;
+mod preprocessor;
+mod reflection;
 mod section_ii;
 mod shader;
+mod shapes;
+mod uniform;
+mod vertex;
+mod viewer;
+
+use cli::{Command, Model};
+use framework::{boxed, Chapter};
+use std::process;
+
+const CHAPTERS: &[Chapter] = &[
+    Chapter {
+        key: "ch_1",
+        description: "Hello Triangle: two triangles with shaders compiled from inline strings",
+        init: boxed::<ch_1::HelloTriangle>,
+    },
+    Chapter {
+        key: "ch_2",
+        description: "Fragment Position: a triangle shaded by its window position",
+        init: boxed::<ch_2::HelloColor>,
+    },
+    Chapter {
+        key: "ch_2_va",
+        description: "Vertex Attributes: per-vertex colors interpolated across a triangle",
+        init: boxed::<ch_2::vertex_attributes::VertexAttributes>,
+    },
+    Chapter {
+        key: "ch_3",
+        description: "CPU Position Offset: moving a triangle by rewriting its vertex buffer",
+        init: boxed::<ch_3::part1::CpuPositionOffset>,
+    },
+    Chapter {
+        key: "ch_3_2",
+        description: "Shader Position Offset: moving a triangle with an offset uniform",
+        init: boxed::<ch_3::part2::VertPositionOffset>,
+    },
+    Chapter {
+        key: "ch_3_3",
+        description: "Shader Calculated Offset: the vertex shader computes the motion from time",
+        init: boxed::<ch_3::part3::VertCalcOffset>,
+    },
+    Chapter {
+        key: "ch_3_4",
+        description: "Fragment Change Color: the color also changes over time",
+        init: boxed::<ch_3::part4::FragChangeColor>,
+    },
+    Chapter {
+        key: "ch_4_1",
+        description: "Orthographic Cube: a prism with back-face culling and no perspective",
+        init: boxed::<ch_4::part1::OrthoCube>,
+    },
+    Chapter {
+        key: "ch_4_2",
+        description: "Shader Perspective: perspective projection computed in the shader",
+        init: boxed::<ch_4::part2::ShaderPerspective>,
+    },
+    Chapter {
+        key: "ch_4_3",
+        description: "Matrix Perspective: the same projection as a matrix uniform",
+        init: boxed::<ch_4::part3::MatrixPerspective>,
+    },
+    Chapter {
+        key: "ch_4_4",
+        description: "Aspect Ratio: the projection keeps up with window resizes",
+        init: boxed::<ch_4::part4::AspectRatio>,
+    },
+    Chapter {
+        key: "ch_5_1",
+        description: "Overlap No Depth: two overlapping objects drawn without depth testing",
+        init: boxed::<section_ii::ch_5_1::OverlapNoDepth>,
+    },
+    Chapter {
+        key: "ch_5_2",
+        description: "Base Vertex Overlap: both objects from one vertex array with glDrawElementsBaseVertex",
+        init: boxed::<section_ii::ch_5_2::BaseVertexOverlap>,
+    },
+    Chapter {
+        key: "ch_5_3",
+        description: "Depth Buffer: the same objects with depth testing, so the nearer one wins",
+        init: boxed::<section_ii::ch_5_3::DepthBuffer>,
+    },
+    Chapter {
+        key: "ch_5_4",
+        description: "Vertex Clipping: the objects move through the near plane and get cut open",
+        init: boxed::<section_ii::ch_5_4::VertexClipping>,
+    },
+    Chapter {
+        key: "ch_5_5",
+        description: "Depth Clamping: the same motion with GL_DEPTH_CLAMP; Space toggles it",
+        init: boxed::<section_ii::ch_5_5::DepthClamping>,
+    },
+    Chapter {
+        key: "ch_6_1",
+        description: "Translation: cubes moved along paths by their model-to-camera matrices",
+        init: boxed::<section_ii::ch_6_1::Translation>,
+    },
+    Chapter {
+        key: "ch_6_2",
+        description: "Scale: cubes scaled uniformly and along single axes, some pulsing",
+        init: boxed::<section_ii::ch_6_2::Scale>,
+    },
+    Chapter {
+        key: "ch_6_3",
+        description: "Rotation: cubes turning about X, Y, Z and an arbitrary axis",
+        init: boxed::<section_ii::ch_6_3::Rotation>,
+    },
+];
+
+/// The index of `key` in `CHAPTERS`, or exit with a hint.
+fn find(key: &str) -> usize {
+    CHAPTERS
+        .iter()
+        .position(|chapter| chapter.key == key)
+        .unwrap_or_else(|| {
+            eprintln!("Unknown chapter {}, see `gltut list`", key);
+            process::exit(2)
+        })
+}
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    println!("Args: {:?}", args);
-    match args[1].as_str() {
-        "ch_1" => ch_1::main_hello_triangle(),
-        "ch_2" => ch_2::main_hello_color(),
-        "ch_2_va" => ch_2::vertex_attributes::main(),
-        "ch_3" => ch_3::part1::main(),
-        "ch_3_2" => ch_3::part2::main(),
-        "ch_3_3" => ch_3::part3::main(),
-        "ch_3_4" => ch_3::part4::main(),
-        "ch_4_1" => ch_4::part1::main(),
-        "ch_4_2" => ch_4::part2::main(),
-        "ch_4_3" => ch_4::part3::main(),
-        "ch_4_4" => ch_4::part4::main(),
-        "ch_5_1" => section_ii::ch_5_1::main(),
-        _ => println!("Unimplemented"),
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = cli::parse(&args).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, cli::USAGE);
+        process::exit(2)
+    });
+
+    match command {
+        Command::Help => print!("{}", cli::USAGE),
+        Command::List => {
+            for chapter in CHAPTERS {
+                println!("{:<10}{}", chapter.key, chapter.description);
+            }
+        }
+        Command::Run {
+            chapter,
+            options,
+            capture,
+        } => {
+            let index = find(&chapter);
+            match capture {
+                Some(capture) => headless::run(&capture, &options, &CHAPTERS[index]),
+                None => framework::run(&options, &CHAPTERS[index..=index], 0),
+            }
+        }
+        Command::View {
+            model,
+            options,
+            capture,
+        } => {
+            let data = match model {
+                Model::File(path) => loaders::load(&path).unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    process::exit(1)
+                }),
+                Model::Shape(shape) => shape().into_mesh_data(),
+            };
+            let chapter = viewer::chapter(data);
+            match capture {
+                Some(capture) => headless::run(&capture, &options, &chapter),
+                None => framework::run(&options, std::slice::from_ref(&chapter), 0),
+            }
+        }
+        Command::Demo { chapter, options } => {
+            let first = chapter.map_or(0, |chapter| find(&chapter));
+            framework::run(&options, CHAPTERS, first)
+        }
     }
 }
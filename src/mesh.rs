@@ -0,0 +1,810 @@
+//! Meshes: vertex attributes, indices and the draw calls that use them.
+//!
+//! Modeled on the `Mesh` class of the gltut framework. The data is described
+//! on the CPU by a [`MeshData`], which can be built in code or loaded from a
+//! file and checked without a GL context, and [`Mesh::new`] uploads it. The
+//! attributes go into one planar buffer, all the values of one attribute
+//! after the other, and every index array into one element buffer.
+//!
+//! `render` issues all the draw commands with every attribute enabled.
+//! Named vertex arrays pick a subset of the attributes, e.g. a "flat" one
+//! without the colors, and can start further into the arrays so that one
+//! buffer can hold several objects; `render_named` draws with one of those.
+//! `render_base_vertex` gets to the same objects with the one vertex array,
+//! by moving the indices instead.
+//! Sub-meshes name a run of the commands, such as one part of a model, for
+//! `render_sub_mesh` to draw on its own.
+extern crate gl;
+use self::gl::types::*;
+
+use crate::objects::{ArrayBuffer, ElementBuffer, VertexArray};
+use crate::vertex::{align, Attribute, ComponentType, VertexLayout};
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::os::raw::c_void;
+
+/// The values of one attribute, every component of every vertex.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Values {
+    F32(Vec<f32>),
+    I8(Vec<i8>),
+    U8(Vec<u8>),
+    I16(Vec<i16>),
+    U16(Vec<u16>),
+    I32(Vec<i32>),
+    U32(Vec<u32>),
+}
+
+macro_rules! values {
+    ($($ty:ty => $variant:ident),*) => {
+        impl Values {
+            pub fn kind(&self) -> ComponentType {
+                match self {
+                    $(Values::$variant(_) => ComponentType::$variant,)*
+                }
+            }
+
+            pub fn len(&self) -> usize {
+                match self {
+                    $(Values::$variant(values) => values.len(),)*
+                }
+            }
+
+            /// Copy the values into `buffer`, `offset` bytes in.
+            fn write(&self, buffer: &ArrayBuffer, offset: usize) {
+                match self {
+                    $(Values::$variant(values) => buffer.update(offset, values),)*
+                }
+            }
+        }
+
+        $(
+            impl From<Vec<$ty>> for Values {
+                fn from(values: Vec<$ty>) -> Values {
+                    Values::$variant(values)
+                }
+            }
+        )*
+    };
+}
+
+values!(f32 => F32, i8 => I8, u8 => U8, i16 => I16, u16 => U16, i32 => I32, u32 => U32);
+
+/// The values of the vertex attribute at `location`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeArray {
+    pub location: u32,
+    /// Values per vertex, 1 to 4.
+    pub components: usize,
+    /// Scale integers to [0, 1] or [-1, 1] instead of converting them as is.
+    pub normalized: bool,
+    pub values: Values,
+}
+
+impl AttributeArray {
+    pub fn new<V: Into<Values>>(location: u32, components: usize, values: V) -> AttributeArray {
+        AttributeArray {
+            location,
+            components,
+            normalized: false,
+            values: values.into(),
+        }
+    }
+
+    fn attribute(&self) -> Attribute {
+        Attribute {
+            location: self.location,
+            components: self.components,
+            kind: self.values.kind(),
+            normalized: self.normalized,
+            offset: None,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Indices {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::U8(indices) => indices.len(),
+            Indices::U16(indices) => indices.len(),
+            Indices::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            Indices::U8(_) => gl::UNSIGNED_BYTE,
+            Indices::U16(_) => gl::UNSIGNED_SHORT,
+            Indices::U32(_) => gl::UNSIGNED_INT,
+        }
+    }
+
+    /// Size in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            Indices::U8(indices) => indices.len(),
+            Indices::U16(indices) => indices.len() * 2,
+            Indices::U32(indices) => indices.len() * 4,
+        }
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self {
+            Indices::U8(indices) => Box::new(indices.iter().map(|&i| u32::from(i))),
+            Indices::U16(indices) => Box::new(indices.iter().map(|&i| u32::from(i))),
+            Indices::U32(indices) => Box::new(indices.iter().cloned()),
+        }
+    }
+
+    fn write(&self, buffer: &ElementBuffer, offset: usize) {
+        match self {
+            Indices::U8(indices) => buffer.update(offset, indices),
+            Indices::U16(indices) => buffer.update(offset, indices),
+            Indices::U32(indices) => buffer.update(offset, indices),
+        }
+    }
+}
+
+/// One draw call.
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// `glDrawArrays` of `count` vertices starting at `first`.
+    Arrays {
+        primitive: GLenum,
+        first: usize,
+        count: usize,
+    },
+    /// `glDrawElements`, with primitive restart on `restart` if it's set.
+    Elements {
+        primitive: GLenum,
+        indices: Indices,
+        restart: Option<u32>,
+    },
+}
+
+/// A vertex array with only some of the attributes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamedVertexArray {
+    pub name: String,
+    pub locations: Vec<u32>,
+    /// The vertex the arrays start at, and so what index 0 refers to.
+    pub first_vertex: usize,
+}
+
+/// Some of the commands, drawn together.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubMesh {
+    pub name: String,
+    /// Indices into `MeshData::commands`.
+    pub commands: Range<usize>,
+}
+
+/// Everything a [`Mesh`] is made of, before it's uploaded.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshData {
+    pub attributes: Vec<AttributeArray>,
+    pub vertex_arrays: Vec<NamedVertexArray>,
+    pub commands: Vec<Command>,
+    pub sub_meshes: Vec<SubMesh>,
+}
+
+impl MeshData {
+    /// Check that the parts fit together, and count the vertices.
+    pub fn validate(&self) -> Result<usize, MeshError> {
+        let mut vertex_count = None;
+        for (i, array) in self.attributes.iter().enumerate() {
+            if !(1..=4).contains(&array.components) {
+                return Err(MeshError::Components {
+                    location: array.location,
+                    components: array.components,
+                });
+            }
+            if array.values.len() % array.components != 0 {
+                return Err(MeshError::PartialVertex {
+                    location: array.location,
+                    values: array.values.len(),
+                    components: array.components,
+                });
+            }
+            if self.attributes[..i]
+                .iter()
+                .any(|other| other.location == array.location)
+            {
+                return Err(MeshError::DuplicateLocation(array.location));
+            }
+            let count = array.values.len() / array.components;
+            match vertex_count {
+                Some(expected) if count != expected => {
+                    return Err(MeshError::VertexCount {
+                        location: array.location,
+                        count,
+                        expected,
+                    })
+                }
+                _ => vertex_count = Some(count),
+            }
+        }
+        let vertex_count = vertex_count.ok_or(MeshError::NoAttributes)?;
+        if vertex_count == 0 {
+            return Err(MeshError::NoVertices);
+        }
+
+        for (i, vao) in self.vertex_arrays.iter().enumerate() {
+            if self.vertex_arrays[..i]
+                .iter()
+                .any(|other| other.name == vao.name)
+            {
+                return Err(MeshError::DuplicateVertexArray(vao.name.clone()));
+            }
+            if let Some(&location) = vao
+                .locations
+                .iter()
+                .find(|&&l| self.attributes.iter().all(|a| a.location != l))
+            {
+                return Err(MeshError::UnknownLocation {
+                    vertex_array: vao.name.clone(),
+                    location,
+                });
+            }
+        }
+
+        // Every vertex array draws every command, so each command has to
+        // stay inside the one that starts furthest in.
+        let available = vertex_count
+            - self
+                .vertex_arrays
+                .iter()
+                .map(|vao| vao.first_vertex.min(vertex_count))
+                .max()
+                .unwrap_or(0);
+        for (command, draw) in self.commands.iter().enumerate() {
+            match draw {
+                Command::Arrays { first, count, .. } => {
+                    if first + count > available {
+                        return Err(MeshError::OutOfRange {
+                            command,
+                            vertex: first + count - 1,
+                            available,
+                        });
+                    }
+                }
+                Command::Elements {
+                    indices, restart, ..
+                } => {
+                    let out_of_range = indices
+                        .iter()
+                        .filter(|index| Some(*index) != *restart)
+                        .find(|&index| index as usize >= available);
+                    if let Some(index) = out_of_range {
+                        return Err(MeshError::OutOfRange {
+                            command,
+                            vertex: index as usize,
+                            available,
+                        });
+                    }
+                }
+            }
+        }
+
+        for (i, sub_mesh) in self.sub_meshes.iter().enumerate() {
+            if self.sub_meshes[..i]
+                .iter()
+                .any(|other| other.name == sub_mesh.name)
+            {
+                return Err(MeshError::DuplicateSubMesh(sub_mesh.name.clone()));
+            }
+            if sub_mesh.commands.start > sub_mesh.commands.end
+                || sub_mesh.commands.end > self.commands.len()
+            {
+                return Err(MeshError::SubMeshRange {
+                    name: sub_mesh.name.clone(),
+                    commands: sub_mesh.commands.clone(),
+                    available: self.commands.len(),
+                });
+            }
+        }
+        Ok(vertex_count)
+    }
+
+    /// The planar layout the attributes are uploaded with.
+    fn layout(&self) -> VertexLayout {
+        self.attributes
+            .iter()
+            .fold(VertexLayout::planar(), |layout, array| {
+                layout.push(array.attribute())
+            })
+    }
+}
+
+/// Why a [`MeshData`] can't be made into a mesh.
+#[derive(Debug, PartialEq)]
+pub enum MeshError {
+    NoAttributes,
+    NoVertices,
+    Components {
+        location: u32,
+        components: usize,
+    },
+    /// The number of values isn't a multiple of the components.
+    PartialVertex {
+        location: u32,
+        values: usize,
+        components: usize,
+    },
+    /// Two attributes have a different number of vertices.
+    VertexCount {
+        location: u32,
+        count: usize,
+        expected: usize,
+    },
+    DuplicateLocation(u32),
+    DuplicateVertexArray(String),
+    UnknownLocation {
+        vertex_array: String,
+        location: u32,
+    },
+    /// A command uses a vertex past the end of the arrays.
+    OutOfRange {
+        command: usize,
+        vertex: usize,
+        available: usize,
+    },
+    DuplicateSubMesh(String),
+    /// A sub-mesh uses commands that don't exist.
+    SubMeshRange {
+        name: String,
+        commands: Range<usize>,
+        available: usize,
+    },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MeshError::NoAttributes => write!(f, "Mesh has no attributes"),
+            MeshError::NoVertices => write!(f, "Mesh has no vertices"),
+            MeshError::Components {
+                location,
+                components,
+            } => write!(
+                f,
+                "Attribute {} has {} components, it needs 1 to 4",
+                location, components
+            ),
+            MeshError::PartialVertex {
+                location,
+                values,
+                components,
+            } => write!(
+                f,
+                "Attribute {} has {} values, which isn't a multiple of its {} components",
+                location, values, components
+            ),
+            MeshError::VertexCount {
+                location,
+                count,
+                expected,
+            } => write!(
+                f,
+                "Attribute {} has {} vertices, the ones before it have {}",
+                location, count, expected
+            ),
+            MeshError::DuplicateLocation(location) => {
+                write!(f, "Attribute {} is defined twice", location)
+            }
+            MeshError::DuplicateVertexArray(name) => {
+                write!(f, "Vertex array \"{}\" is defined twice", name)
+            }
+            MeshError::UnknownLocation {
+                vertex_array,
+                location,
+            } => write!(
+                f,
+                "Vertex array \"{}\" uses attribute {}, which isn't defined",
+                vertex_array, location
+            ),
+            MeshError::OutOfRange {
+                command,
+                vertex,
+                available,
+            } => write!(
+                f,
+                "Command {} uses vertex {}, but there are only {}",
+                command, vertex, available
+            ),
+            MeshError::DuplicateSubMesh(name) => {
+                write!(f, "Sub-mesh \"{}\" is defined twice", name)
+            }
+            MeshError::SubMeshRange {
+                name,
+                commands,
+                available,
+            } => write!(
+                f,
+                "Sub-mesh \"{}\" uses commands {:?}, but there are only {}",
+                name, commands, available
+            ),
+        }
+    }
+}
+
+impl Error for MeshError {}
+
+/// A command as uploaded.
+enum Draw {
+    Arrays {
+        primitive: GLenum,
+        first: GLint,
+        count: GLsizei,
+    },
+    Elements {
+        primitive: GLenum,
+        count: GLsizei,
+        kind: GLenum,
+        /// Bytes into the element buffer.
+        offset: usize,
+        restart: Option<u32>,
+    },
+}
+
+/// Buffers, vertex arrays and draw commands, ready to render.
+pub struct Mesh {
+    vao: VertexArray,
+    named: Vec<(String, VertexArray)>,
+    draws: Vec<Draw>,
+    sub_meshes: Vec<SubMesh>,
+    // Only referenced by the vertex arrays, but they have to live as long.
+    #[allow(dead_code)]
+    vertices: ArrayBuffer,
+    #[allow(dead_code)]
+    indices: Option<ElementBuffer>,
+}
+
+impl Mesh {
+    pub fn new(data: &MeshData) -> Result<Mesh, MeshError> {
+        let vertex_count = data.validate()?;
+        let layout = data.layout();
+        let pointers = layout.pointers(vertex_count, 0);
+
+        let mut vertices = ArrayBuffer::new();
+        vertices.upload(
+            &vec![0u8; layout.buffer_size(vertex_count)],
+            gl::STATIC_DRAW,
+        );
+        for (array, pointer) in data.attributes.iter().zip(&pointers) {
+            array.values.write(&vertices, pointer.offset);
+        }
+
+        let mut draws = Vec::new();
+        let mut index_data = Vec::new();
+        let mut index_size = 0;
+        for command in &data.commands {
+            draws.push(match command {
+                Command::Arrays {
+                    primitive,
+                    first,
+                    count,
+                } => Draw::Arrays {
+                    primitive: *primitive,
+                    first: *first as GLint,
+                    count: *count as GLsizei,
+                },
+                Command::Elements {
+                    primitive,
+                    indices,
+                    restart,
+                } => {
+                    index_data.push((indices, index_size));
+                    let draw = Draw::Elements {
+                        primitive: *primitive,
+                        count: indices.len() as GLsizei,
+                        kind: indices.gl_enum(),
+                        offset: index_size,
+                        restart: *restart,
+                    };
+                    index_size += align(indices.size());
+                    draw
+                }
+            });
+        }
+        let indices = if index_data.is_empty() {
+            None
+        } else {
+            let mut buffer = ElementBuffer::new();
+            buffer.upload(&vec![0u8; index_size], gl::STATIC_DRAW);
+            for (indices, offset) in index_data {
+                indices.write(&buffer, offset);
+            }
+            Some(buffer)
+        };
+
+        let vertex_array = |locations: &[u32], first_vertex: usize| {
+            let vao = VertexArray::new();
+            {
+                let bound = vao.bind();
+                for pointer in layout.pointers(vertex_count, first_vertex) {
+                    if locations.contains(&pointer.attribute.location) {
+                        pointer.apply(&bound, &vertices);
+                    }
+                }
+                if let Some(indices) = &indices {
+                    bound.element_buffer(indices);
+                }
+            }
+            vao
+        };
+
+        let all: Vec<u32> = pointers
+            .iter()
+            .map(|pointer| pointer.attribute.location)
+            .collect();
+        let vao = vertex_array(&all, 0);
+        let named = data
+            .vertex_arrays
+            .iter()
+            .map(|named| {
+                (
+                    named.name.clone(),
+                    vertex_array(&named.locations, named.first_vertex),
+                )
+            })
+            .collect();
+
+        Ok(Mesh {
+            vao,
+            named,
+            draws,
+            sub_meshes: data.sub_meshes.clone(),
+            vertices,
+            indices,
+        })
+    }
+
+    /// Issue every command with all the attributes.
+    pub fn render(&self) {
+        self.draw(&self.vao, &self.draws, 0);
+    }
+
+    /// Issue every command as if the arrays started `base_vertex` vertices
+    /// later, with `glDrawElementsBaseVertex`. This is how one vertex array
+    /// can draw several objects that share the same indices.
+    ///
+    /// The indices plus `base_vertex` have to stay within the vertices.
+    pub fn render_base_vertex(&self, base_vertex: i32) {
+        self.draw(&self.vao, &self.draws, base_vertex);
+    }
+
+    /// Issue every command with the vertex array called `name`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no such vertex array.
+    pub fn render_named(&self, name: &str) {
+        let vao = self
+            .named
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, vao)| vao)
+            .unwrap_or_else(|| panic!("Mesh has no vertex array named \"{}\"", name));
+        self.draw(vao, &self.draws, 0);
+    }
+
+    /// Issue the commands of the sub-mesh called `name`, with all the
+    /// attributes.
+    ///
+    /// # Panics
+    ///
+    /// If there is no such sub-mesh.
+    pub fn render_sub_mesh(&self, name: &str) {
+        let sub_mesh = self
+            .sub_meshes
+            .iter()
+            .find(|sub_mesh| sub_mesh.name == name)
+            .unwrap_or_else(|| panic!("Mesh has no sub-mesh named \"{}\"", name));
+        self.draw(&self.vao, &self.draws[sub_mesh.commands.clone()], 0);
+    }
+
+    pub fn sub_meshes(&self) -> &[SubMesh] {
+        &self.sub_meshes
+    }
+
+    /// The vertex array `render` uses.
+    pub fn vertex_array(&self) -> &VertexArray {
+        &self.vao
+    }
+
+    /// The vertex array `render` uses, then the named ones.
+    pub fn vertex_arrays(&self) -> impl Iterator<Item = &VertexArray> {
+        std::iter::once(&self.vao).chain(self.named.iter().map(|(_, vao)| vao))
+    }
+
+    fn draw(&self, vao: &VertexArray, draws: &[Draw], base_vertex: GLint) {
+        let _vao = vao.bind();
+        for draw in draws {
+            match *draw {
+                Draw::Arrays {
+                    primitive,
+                    first,
+                    count,
+                } => unsafe { gl::DrawArrays(primitive, first + base_vertex, count) },
+                Draw::Elements {
+                    primitive,
+                    count,
+                    kind,
+                    offset,
+                    restart,
+                } => unsafe {
+                    if let Some(index) = restart {
+                        gl::Enable(gl::PRIMITIVE_RESTART);
+                        gl::PrimitiveRestartIndex(index);
+                    }
+                    let offset = offset as *const c_void;
+                    if base_vertex == 0 {
+                        gl::DrawElements(primitive, count, kind, offset);
+                    } else {
+                        gl::DrawElementsBaseVertex(primitive, count, kind, offset, base_vertex);
+                    }
+                    if restart.is_some() {
+                        gl::Disable(gl::PRIMITIVE_RESTART);
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A quad made of two triangles, with a position-only vertex array and
+    /// one sub-mesh.
+    fn quad() -> MeshData {
+        MeshData {
+            attributes: vec![
+                AttributeArray::new(0, 3, vec![0.0f32; 12]),
+                AttributeArray::new(1, 4, vec![255u8; 16]),
+            ],
+            vertex_arrays: vec![NamedVertexArray {
+                name: "flat".to_string(),
+                locations: vec![0],
+                first_vertex: 0,
+            }],
+            commands: vec![Command::Elements {
+                primitive: gl::TRIANGLES,
+                indices: Indices::U16(vec![0, 1, 2, 2, 3, 0]),
+                restart: None,
+            }],
+            sub_meshes: vec![SubMesh {
+                name: "quad".to_string(),
+                commands: 0..1,
+            }],
+        }
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(quad().validate(), Ok(4));
+    }
+
+    #[test]
+    fn partial_vertex() {
+        let mut data = quad();
+        data.attributes[0].values = Values::F32(vec![0.0; 11]);
+        assert_eq!(
+            data.validate(),
+            Err(MeshError::PartialVertex {
+                location: 0,
+                values: 11,
+                components: 3
+            })
+        );
+    }
+
+    #[test]
+    fn vertex_count() {
+        let mut data = quad();
+        data.attributes[1].values = Values::U8(vec![255; 12]);
+        assert_eq!(
+            data.validate(),
+            Err(MeshError::VertexCount {
+                location: 1,
+                count: 3,
+                expected: 4
+            })
+        );
+    }
+
+    #[test]
+    fn duplicate_location() {
+        let mut data = quad();
+        data.attributes[1].location = 0;
+        assert_eq!(data.validate(), Err(MeshError::DuplicateLocation(0)));
+    }
+
+    #[test]
+    fn unknown_location() {
+        let mut data = quad();
+        data.vertex_arrays[0].locations.push(2);
+        assert_eq!(
+            data.validate(),
+            Err(MeshError::UnknownLocation {
+                vertex_array: "flat".to_string(),
+                location: 2
+            })
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut data = quad();
+        data.commands[0] = Command::Elements {
+            primitive: gl::TRIANGLE_STRIP,
+            indices: Indices::U8(vec![0, 1, 255, 2, 3, 4]),
+            restart: Some(255),
+        };
+        // The restart index isn't a vertex, 4 is one too many.
+        assert_eq!(
+            data.validate(),
+            Err(MeshError::OutOfRange {
+                command: 0,
+                vertex: 4,
+                available: 4
+            })
+        );
+
+        let mut data = quad();
+        data.commands.push(Command::Arrays {
+            primitive: gl::POINTS,
+            first: 2,
+            count: 3,
+        });
+        assert_eq!(
+            data.validate(),
+            Err(MeshError::OutOfRange {
+                command: 1,
+                vertex: 4,
+                available: 4
+            })
+        );
+    }
+
+    #[test]
+    fn out_of_range_of_a_later_vertex_array() {
+        let mut data = quad();
+        data.vertex_arrays[0].first_vertex = 2;
+        assert_eq!(
+            data.validate(),
+            Err(MeshError::OutOfRange {
+                command: 0,
+                vertex: 2,
+                available: 2
+            })
+        );
+    }
+
+    #[test]
+    fn sub_mesh_range() {
+        let mut data = quad();
+        data.sub_meshes[0].commands = 0..2;
+        assert_eq!(
+            data.validate(),
+            Err(MeshError::SubMeshRange {
+                name: "quad".to_string(),
+                commands: 0..2,
+                available: 1
+            })
+        );
+    }
+}
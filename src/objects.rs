@@ -0,0 +1,308 @@
+//! Owned GL objects.
+//!
+//! Buffers, vertex arrays and programs delete themselves when dropped, and
+//! binding one returns a guard that puts the previous binding back when it
+//! goes out of scope. Like everything else that touches GL, they need the
+//! context that made them to be current.
+extern crate gl;
+use self::gl::types::*;
+
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::c_void;
+
+/// The binding point a [`Buffer`] is made for.
+pub trait BufferTarget {
+    const TARGET: GLenum;
+    /// The `glGet` name of the current binding.
+    const BINDING: GLenum;
+}
+
+/// Vertex data, `GL_ARRAY_BUFFER`.
+pub enum Array {}
+
+/// Indices, `GL_ELEMENT_ARRAY_BUFFER`.
+pub enum ElementArray {}
+
+impl BufferTarget for Array {
+    const TARGET: GLenum = gl::ARRAY_BUFFER;
+    const BINDING: GLenum = gl::ARRAY_BUFFER_BINDING;
+}
+
+impl BufferTarget for ElementArray {
+    const TARGET: GLenum = gl::ELEMENT_ARRAY_BUFFER;
+    const BINDING: GLenum = gl::ELEMENT_ARRAY_BUFFER_BINDING;
+}
+
+pub type ArrayBuffer = Buffer<Array>;
+pub type ElementBuffer = Buffer<ElementArray>;
+
+/// A buffer object that is only ever bound to `T`.
+pub struct Buffer<T: BufferTarget> {
+    id: u32,
+    /// Size of the data store in bytes.
+    size: usize,
+    target: PhantomData<T>,
+}
+
+impl<T: BufferTarget> Buffer<T> {
+    /// A buffer without a data store yet.
+    pub fn new() -> Buffer<T> {
+        let mut id = 0;
+        unsafe { gl::GenBuffers(1, &mut id) };
+        Buffer {
+            id,
+            size: 0,
+            target: PhantomData,
+        }
+    }
+
+    /// A buffer holding a copy of `data`. `usage` is a hint such as
+    /// `gl::STATIC_DRAW`.
+    pub fn with_data<D: Copy>(data: &[D], usage: GLenum) -> Buffer<T> {
+        let mut buffer = Buffer::new();
+        buffer.upload(data, usage);
+        buffer
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Size of the data store in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn bind(&self) -> BufferBinding<'_, T> {
+        let previous = binding(T::BINDING);
+        unsafe { gl::BindBuffer(T::TARGET, self.id) };
+        BufferBinding {
+            previous,
+            buffer: PhantomData,
+        }
+    }
+
+    /// Replace the data store with a copy of `data`.
+    pub fn upload<D: Copy>(&mut self, data: &[D], usage: GLenum) {
+        let size = mem::size_of_val(data);
+        {
+            let _bound = self.bind();
+            unsafe {
+                gl::BufferData(
+                    T::TARGET,
+                    size as GLsizeiptr,
+                    data.as_ptr() as *const c_void,
+                    usage,
+                )
+            };
+        }
+        self.size = size;
+    }
+
+    /// Overwrite part of the data store, starting `offset` bytes in.
+    ///
+    /// # Panics
+    ///
+    /// If `data` doesn't fit in the store.
+    pub fn update<D: Copy>(&self, offset: usize, data: &[D]) {
+        let size = mem::size_of_val(data);
+        assert!(
+            offset + size <= self.size,
+            "Updating bytes {}..{} of a {} byte buffer",
+            offset,
+            offset + size,
+            self.size
+        );
+        let _bound = self.bind();
+        unsafe {
+            gl::BufferSubData(
+                T::TARGET,
+                offset as GLintptr,
+                size as GLsizeiptr,
+                data.as_ptr() as *const c_void,
+            )
+        };
+    }
+}
+
+impl<T: BufferTarget> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.id) }
+    }
+}
+
+/// `Buffer::bind`'s guard.
+pub struct BufferBinding<'a, T: BufferTarget> {
+    previous: u32,
+    buffer: PhantomData<&'a Buffer<T>>,
+}
+
+impl<T: BufferTarget> Drop for BufferBinding<'_, T> {
+    fn drop(&mut self) {
+        unsafe { gl::BindBuffer(T::TARGET, self.previous) }
+    }
+}
+
+pub struct VertexArray {
+    id: u32,
+}
+
+impl VertexArray {
+    pub fn new() -> VertexArray {
+        let mut id = 0;
+        unsafe { gl::GenVertexArrays(1, &mut id) };
+        VertexArray { id }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Bind for drawing, or to describe where the attributes come from.
+    pub fn bind(&self) -> VertexArrayBinding<'_> {
+        let previous = binding(gl::VERTEX_ARRAY_BINDING);
+        unsafe { gl::BindVertexArray(self.id) };
+        VertexArrayBinding {
+            previous,
+            vertex_array: PhantomData,
+        }
+    }
+}
+
+impl Drop for VertexArray {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteVertexArrays(1, &self.id) }
+    }
+}
+
+/// `VertexArray::bind`'s guard.
+pub struct VertexArrayBinding<'a> {
+    previous: u32,
+    vertex_array: PhantomData<&'a VertexArray>,
+}
+
+impl VertexArrayBinding<'_> {
+    /// Feed attribute `location` with `components` floats per vertex read
+    /// from `buffer`, starting `offset` bytes in and `stride` bytes apart
+    /// (0 for tightly packed).
+    ///
+    /// # Panics
+    ///
+    /// If `offset` is outside of `buffer`.
+    pub fn attribute(
+        &self,
+        location: u32,
+        buffer: &ArrayBuffer,
+        components: i32,
+        stride: usize,
+        offset: usize,
+    ) {
+        self.attribute_pointer(
+            location,
+            buffer,
+            components,
+            gl::FLOAT,
+            false,
+            stride,
+            offset,
+        )
+    }
+
+    /// Like `attribute`, with components of type `kind`, e.g.
+    /// `gl::UNSIGNED_BYTE`. Integers reach the shader as floats, scaled to
+    /// [0, 1] or [-1, 1] when `normalized`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn attribute_pointer(
+        &self,
+        location: u32,
+        buffer: &ArrayBuffer,
+        components: i32,
+        kind: GLenum,
+        normalized: bool,
+        stride: usize,
+        offset: usize,
+    ) {
+        assert!(
+            offset < buffer.size(),
+            "Attribute {} starts at byte {} of a {} byte buffer",
+            location,
+            offset,
+            buffer.size()
+        );
+        let _bound = buffer.bind();
+        unsafe {
+            gl::EnableVertexAttribArray(location);
+            gl::VertexAttribPointer(
+                location,
+                components,
+                kind,
+                normalized as GLboolean,
+                stride as GLsizei,
+                offset as *const c_void,
+            );
+        }
+    }
+
+    /// Take indices from `buffer`. The binding is part of the vertex array,
+    /// so unlike other buffer bindings it stays after this returns.
+    pub fn element_buffer(&self, buffer: &ElementBuffer) {
+        unsafe { gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, buffer.id()) }
+    }
+}
+
+impl Drop for VertexArrayBinding<'_> {
+    fn drop(&mut self) {
+        unsafe { gl::BindVertexArray(self.previous) }
+    }
+}
+
+/// A linked program object.
+pub struct Program {
+    id: u32,
+}
+
+impl Program {
+    /// Take ownership of `id`, which is deleted with the `Program`.
+    pub fn from_raw(id: u32) -> Program {
+        Program { id }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Use the program for drawing and setting uniforms.
+    pub fn bind(&self) -> ProgramBinding<'_> {
+        let previous = binding(gl::CURRENT_PROGRAM);
+        unsafe { gl::UseProgram(self.id) };
+        ProgramBinding {
+            previous,
+            program: PhantomData,
+        }
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteProgram(self.id) }
+    }
+}
+
+/// `Program::bind`'s guard.
+pub struct ProgramBinding<'a> {
+    previous: u32,
+    program: PhantomData<&'a Program>,
+}
+
+impl Drop for ProgramBinding<'_> {
+    fn drop(&mut self) {
+        unsafe { gl::UseProgram(self.previous) }
+    }
+}
+
+fn binding(name: GLenum) -> u32 {
+    let mut id = 0;
+    unsafe { gl::GetIntegerv(name, &mut id) };
+    id as u32
+}
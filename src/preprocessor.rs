@@ -0,0 +1,454 @@
+//! `#include` and `#define` support for shader files, done before the source
+//! is handed to GL.
+//!
+//! `#include "file.glsl"` is resolved relative to the including file and
+//! every file is pulled in at most once, so headers don't need their own
+//! guards and cycles can't happen. We remember where every line of the
+//! flattened source came from, so compile errors can be traced back to the
+//! file and line they are about.
+use crate::shader::ShaderError;
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A flattened shader source.
+pub struct Preprocessed {
+    pub code: String,
+    /// Every file that went into `code`. The root file is first.
+    pub files: Vec<PathBuf>,
+    /// For every line of `code`, the index into `files` and the line number
+    /// in that file it came from.
+    lines: Vec<(usize, usize)>,
+}
+
+impl Preprocessed {
+    /// Rewrite the locations in a driver's info log to point at the original
+    /// files, e.g. `0:12(3): error` becomes `common.glsl:4(3): error`.
+    ///
+    /// Only the line number is used: `#line` with a source string number
+    /// would be neater, but Mesa reports some errors against string 0
+    /// regardless.
+    pub fn map_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| match find_location(line) {
+                Some((start, end, number)) if number >= 1 && number <= self.lines.len() => {
+                    let (file, number) = self.lines[number - 1];
+                    format!(
+                        "{}{}:{}{}",
+                        &line[..start],
+                        self.files[file].display(),
+                        number,
+                        &line[end..]
+                    )
+                }
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn push(&mut self, file: usize, number: usize, line: &str) {
+        self.code.push_str(line);
+        self.code.push('\n');
+        self.lines.push((file, number));
+    }
+}
+
+/// Read `path`, expand its includes and put `defines` right after the
+/// `#version` line.
+pub fn preprocess(path: &Path, defines: &[(String, String)]) -> Result<Preprocessed, ShaderError> {
+    let mut preprocessor = Preprocessor::new(defines);
+    preprocessor.file(path, None)?;
+    Ok(preprocessor.output)
+}
+
+/// Like `preprocess`, for source that isn't in a file. `name` stands in for
+/// the file name in errors, and includes are relative to the working
+/// directory.
+pub fn preprocess_str(
+    name: &str,
+    code: &str,
+    defines: &[(String, String)],
+) -> Result<Preprocessed, ShaderError> {
+    let mut preprocessor = Preprocessor::new(defines);
+    preprocessor.output.files.push(PathBuf::from(name));
+    preprocessor.text(Path::new(""), code, 0, None)?;
+    Ok(preprocessor.output)
+}
+
+struct Preprocessor<'a> {
+    output: Preprocessed,
+    included: HashSet<PathBuf>,
+    defines: &'a [(String, String)],
+}
+
+impl<'a> Preprocessor<'a> {
+    fn new(defines: &'a [(String, String)]) -> Preprocessor<'a> {
+        Preprocessor {
+            output: Preprocessed {
+                code: String::new(),
+                files: Vec::new(),
+                lines: Vec::new(),
+            },
+            included: HashSet::new(),
+            defines,
+        }
+    }
+
+    /// Append `path`. `from` is the including file and line, `None` for the
+    /// root file.
+    fn file(&mut self, path: &Path, from: Option<(&Path, usize)>) -> Result<(), ShaderError> {
+        let io_error = |source| ShaderError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+        let text = fs::read_to_string(path).map_err(io_error)?;
+        let canonical = fs::canonicalize(path).map_err(io_error)?;
+        if !self.included.insert(canonical) {
+            return Ok(());
+        }
+        let index = self.output.files.len();
+        self.output.files.push(path.to_path_buf());
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        self.text(directory, &text, index, from)
+    }
+
+    /// Append the contents of `files[index]`, whose includes are relative to
+    /// `directory`.
+    fn text(
+        &mut self,
+        directory: &Path,
+        text: &str,
+        index: usize,
+        from: Option<(&Path, usize)>,
+    ) -> Result<(), ShaderError> {
+        let path = self.output.files[index].clone();
+        // Only comments may come before `#version`, and the defines have to
+        // go after it.
+        let header = text
+            .lines()
+            .take_while(|line| {
+                let line = line.trim_start();
+                line.is_empty() || line.starts_with("//")
+            })
+            .count();
+        let has_version = text
+            .lines()
+            .nth(header)
+            .is_some_and(|line| line.trim_start().starts_with("#version"));
+        if let (Some((parent, line)), true) = (from, has_version) {
+            return Err(ShaderError::Preprocess {
+                path: parent.display().to_string(),
+                line,
+                message: format!("{} has a #version line", path.display()),
+            });
+        }
+        if from.is_none() && !has_version {
+            self.defines(index, 1);
+        }
+
+        for (number, line) in text.lines().enumerate() {
+            let number = number + 1;
+            let directive = line.trim_start();
+            if let Some(rest) = directive.strip_prefix("#include") {
+                let name = parse_include(rest).ok_or_else(|| ShaderError::Preprocess {
+                    path: path.display().to_string(),
+                    line: number,
+                    message: format!("expected #include \"file\", got {}", directive),
+                })?;
+                let included = normalize(&directory.join(name));
+                self.file(&included, Some((&path, number)))?;
+                continue;
+            }
+
+            self.output.push(index, number, line);
+            if from.is_none() && has_version && number == header + 1 {
+                self.defines(index, number);
+            }
+        }
+        Ok(())
+    }
+
+    /// Emit the caller's defines, attributed to `number` of the root file.
+    fn defines(&mut self, index: usize, number: usize) {
+        for (name, value) in self.defines {
+            let define = format!("#define {} {}", name, value);
+            self.output.push(index, number, &define);
+        }
+    }
+}
+
+/// Fold `dir/..` away so error messages show the path a reader expects.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The file name in `"file"`, with nothing but whitespace around it.
+fn parse_include(rest: &str) -> Option<&str> {
+    let rest = rest.trim();
+    if rest.len() >= 2 && rest.starts_with('"') && rest.ends_with('"') {
+        let name = &rest[1..rest.len() - 1];
+        if !name.is_empty() && !name.contains('"') {
+            return Some(name);
+        }
+    }
+    None
+}
+
+/// Find the first `<source>:<line>` or `<source>(<line>)` in a log line, as
+/// written by Mesa/AMD and NVIDIA respectively. Returns the byte range to
+/// replace, from the source string number to the end of the line number
+/// (including the closing parenthesis), and the line number.
+fn find_location(line: &str) -> Option<(usize, usize, usize)> {
+    let bytes = line.as_bytes();
+    let mut start = 0;
+    while start < bytes.len() {
+        let at_word_start = start == 0 || !bytes[start - 1].is_ascii_alphanumeric();
+        if !(at_word_start && bytes[start].is_ascii_digit()) {
+            start += 1;
+            continue;
+        }
+        let source_end = start + count_digits(&bytes[start..]);
+        if source_end + 1 < bytes.len()
+            && (bytes[source_end] == b':' || bytes[source_end] == b'(')
+            && bytes[source_end + 1].is_ascii_digit()
+        {
+            let number_start = source_end + 1;
+            let mut end = number_start + count_digits(&bytes[number_start..]);
+            let number = line[number_start..end].parse().ok()?;
+            if bytes[source_end] == b'(' && bytes.get(end) == Some(&b')') {
+                end += 1;
+            }
+            return Some((start, end, number));
+        }
+        start = source_end;
+    }
+    None
+}
+
+fn count_digits(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|b| b.is_ascii_digit()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for every test, with `files` in it.
+    fn scratch(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "gltut-preprocessor-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        for (name, text) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+        dir
+    }
+
+    fn lines(preprocessed: &Preprocessed) -> Vec<&str> {
+        preprocessed.code.lines().collect()
+    }
+
+    #[test]
+    fn includes_once_by_canonical_path() {
+        let dir = scratch(
+            "once",
+            &[
+                (
+                    "main.vs",
+                    "#version 330\n\
+                     #include \"common.glsl\"\n\
+                     #include \"./common.glsl\"\n\
+                     #include \"lib/../common.glsl\"\n\
+                     #include \"lib/uses_common.glsl\"\n\
+                     void main() {}\n",
+                ),
+                ("common.glsl", "float common;\n"),
+                (
+                    "lib/uses_common.glsl",
+                    "#include \"../common.glsl\"\nfloat lib;\n",
+                ),
+            ],
+        );
+        let preprocessed = preprocess(&dir.join("main.vs"), &[]).unwrap();
+        assert_eq!(
+            lines(&preprocessed),
+            [
+                "#version 330",
+                "float common;",
+                "float lib;",
+                "void main() {}"
+            ]
+        );
+        assert_eq!(
+            preprocessed.files,
+            [
+                dir.join("main.vs"),
+                dir.join("common.glsl"),
+                dir.join("lib/uses_common.glsl"),
+            ]
+        );
+    }
+
+    #[test]
+    fn include_cycles_stop() {
+        let dir = scratch(
+            "cycle",
+            &[
+                (
+                    "main.vs",
+                    "#version 330\n#include \"a.glsl\"\nvoid main() {}\n",
+                ),
+                ("a.glsl", "#include \"b.glsl\"\nfloat a;\n"),
+                ("b.glsl", "#include \"a.glsl\"\nfloat b;\n"),
+            ],
+        );
+        let preprocessed = preprocess(&dir.join("main.vs"), &[]).unwrap();
+        assert_eq!(
+            lines(&preprocessed),
+            ["#version 330", "float b;", "float a;", "void main() {}"]
+        );
+    }
+
+    #[test]
+    fn defines_go_after_version() {
+        let defines = [
+            ("LIGHTS".to_string(), "4".to_string()),
+            ("GAMMA".to_string(), "2.2".to_string()),
+        ];
+        let preprocessed = preprocess_str(
+            "inline.vs",
+            "// A comment may come first.\n\n#version 330\nvoid main() {}\n",
+            &defines,
+        )
+        .unwrap();
+        assert_eq!(
+            lines(&preprocessed),
+            [
+                "// A comment may come first.",
+                "",
+                "#version 330",
+                "#define LIGHTS 4",
+                "#define GAMMA 2.2",
+                "void main() {}",
+            ]
+        );
+
+        // Without a #version line they go first.
+        let preprocessed = preprocess_str("inline.vs", "void main() {}\n", &defines).unwrap();
+        assert_eq!(
+            lines(&preprocessed),
+            ["#define LIGHTS 4", "#define GAMMA 2.2", "void main() {}"]
+        );
+    }
+
+    #[test]
+    fn bad_includes() {
+        let dir = scratch(
+            "bad",
+            &[
+                ("versioned.vs", "#version 330\n\n#include \"header.glsl\"\n"),
+                ("header.glsl", "#version 330\n"),
+                ("unquoted.vs", "#version 330\n#include <header.glsl>\n"),
+            ],
+        );
+        match preprocess(&dir.join("versioned.vs"), &[]) {
+            Err(ShaderError::Preprocess { path, line, .. }) => {
+                assert_eq!(path, dir.join("versioned.vs").display().to_string());
+                assert_eq!(line, 3);
+            }
+            _ => panic!("an included #version should be an error"),
+        }
+        match preprocess(&dir.join("unquoted.vs"), &[]) {
+            Err(ShaderError::Preprocess { line, message, .. }) => {
+                assert_eq!(line, 2);
+                assert!(message.contains("#include <header.glsl>"), "{}", message);
+            }
+            _ => panic!("#include <file> should be an error"),
+        }
+        assert!(matches!(
+            preprocess(&dir.join("missing.vs"), &[]),
+            Err(ShaderError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn logs_point_at_the_original_files() {
+        let dir = scratch(
+            "log",
+            &[
+                (
+                    "main.vs",
+                    "#version 330\n#include \"a.glsl\"\nvoid main() {}\n",
+                ),
+                ("a.glsl", "float a;\n"),
+            ],
+        );
+        let defines = [("N".to_string(), "2".to_string())];
+        // #version, #define N 2, float a, void main
+        let preprocessed = preprocess(&dir.join("main.vs"), &defines).unwrap();
+        let main = dir.join("main.vs").display().to_string();
+        let a = dir.join("a.glsl").display().to_string();
+
+        // Mesa
+        assert_eq!(
+            preprocessed.map_log("0:3(7): error: syntax error"),
+            format!("{}:1(7): error: syntax error", a)
+        );
+        // NVIDIA
+        assert_eq!(
+            preprocessed.map_log("0(4) : error C1008: undefined variable \"x\""),
+            format!("{}:3 : error C1008: undefined variable \"x\"", main)
+        );
+        // The defines belong to the #version line.
+        assert_eq!(
+            preprocessed.map_log("0:2(9): warning: redefined"),
+            format!("{}:1(9): warning: redefined", main)
+        );
+        // Every line of the log, and only the locations.
+        assert_eq!(
+            preprocessed.map_log("0:1(1): one\n0:4(2): vec4:4 two"),
+            format!("{}:1(1): one\n{}:3(2): vec4:4 two", main, main)
+        );
+        // Lines that aren't in the source stay as they are.
+        assert_eq!(
+            preprocessed.map_log("0:99(1): error: nowhere"),
+            "0:99(1): error: nowhere"
+        );
+        assert_eq!(preprocessed.map_log("Linking failed."), "Linking failed.");
+    }
+
+    #[test]
+    fn locations() {
+        assert_eq!(find_location("0:12(3): error"), Some((0, 4, 12)));
+        assert_eq!(find_location("0(12) : error"), Some((0, 5, 12)));
+        assert_eq!(
+            find_location("ERROR: 0:7: 'x' : undeclared"),
+            Some((7, 10, 7))
+        );
+        // Digits inside a word aren't a source string number.
+        assert_eq!(find_location("vec4:3"), None);
+        assert_eq!(find_location("#version 330"), None);
+        assert_eq!(find_location("0:"), None);
+    }
+}
@@ -0,0 +1,308 @@
+//! What a linked program expects from the outside: its active attributes,
+//! uniforms and uniform blocks, as reported by the driver.
+extern crate gl;
+use self::gl::types::*;
+
+use std::fmt;
+
+/// An active attribute or uniform.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variable {
+    /// Name as reported by GL; arrays end in `[0]`.
+    pub name: String,
+    /// GL type, e.g. `gl::FLOAT_VEC4`.
+    pub kind: GLenum,
+    /// Number of elements, 1 unless it's an array.
+    pub size: i32,
+    /// -1 for uniforms that live in a block, and for built-ins.
+    pub location: i32,
+}
+
+/// An active uniform block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    pub name: String,
+    pub index: u32,
+    pub binding: i32,
+    /// Size of the buffer storage the block needs, in bytes.
+    pub data_size: i32,
+    /// The uniforms declared in the block.
+    pub uniforms: Vec<Variable>,
+}
+
+/// A vertex array doesn't feed the attributes a program expects.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutMismatch {
+    /// The program reads `name` from `location`, but that array is disabled.
+    Disabled { name: String, location: u32 },
+    /// The array at `location` is enabled, but no attribute reads it.
+    Unused { location: u32 },
+}
+
+impl fmt::Display for LayoutMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayoutMismatch::Disabled { name, location } => write!(
+                f,
+                "attribute `{}` reads location {}, which isn't enabled",
+                name, location
+            ),
+            LayoutMismatch::Unused { location } => write!(
+                f,
+                "location {} is enabled but no attribute reads it",
+                location
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProgramInfo {
+    pub attributes: Vec<Variable>,
+    /// Uniforms in the default block; block members are in `blocks`.
+    pub uniforms: Vec<Variable>,
+    pub blocks: Vec<Block>,
+}
+
+impl ProgramInfo {
+    /// Query a successfully linked program.
+    pub unsafe fn query(program: u32) -> ProgramInfo {
+        let mut info = ProgramInfo::default();
+
+        let mut count = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count);
+        let mut max_length = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, &mut max_length);
+        for index in 0..count as u32 {
+            let mut variable = active_variable(max_length, |len, length, size, kind, name| {
+                gl::GetActiveAttrib(program, index, len, length, size, kind, name)
+            });
+            variable.location = gl::GetAttribLocation(program, c_name(&variable.name).as_ptr());
+            info.attributes.push(variable);
+        }
+        info.attributes.sort_by_key(|attribute| attribute.location);
+
+        let mut block_count = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut block_count);
+        let mut max_length = 0;
+        gl::GetProgramiv(
+            program,
+            gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH,
+            &mut max_length,
+        );
+        for index in 0..block_count as u32 {
+            let mut name = vec![0u8; max_length.max(1) as usize];
+            let mut length = 0;
+            gl::GetActiveUniformBlockName(
+                program,
+                index,
+                max_length,
+                &mut length,
+                name.as_mut_ptr() as *mut GLchar,
+            );
+            name.truncate(length as usize);
+            let (mut binding, mut data_size) = (0, 0);
+            gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_BINDING, &mut binding);
+            gl::GetActiveUniformBlockiv(
+                program,
+                index,
+                gl::UNIFORM_BLOCK_DATA_SIZE,
+                &mut data_size,
+            );
+            info.blocks.push(Block {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                index,
+                binding,
+                data_size,
+                uniforms: Vec::new(),
+            });
+        }
+
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+        for index in 0..count as u32 {
+            let mut variable = active_variable(max_length, |len, length, size, kind, name| {
+                gl::GetActiveUniform(program, index, len, length, size, kind, name)
+            });
+            let mut block_index = -1;
+            gl::GetActiveUniformsiv(
+                program,
+                1,
+                &index,
+                gl::UNIFORM_BLOCK_INDEX,
+                &mut block_index,
+            );
+            if block_index >= 0 {
+                variable.location = -1;
+                info.blocks[block_index as usize].uniforms.push(variable);
+            } else {
+                variable.location =
+                    gl::GetUniformLocation(program, c_name(&variable.name).as_ptr());
+                info.uniforms.push(variable);
+            }
+        }
+        info.uniforms.sort_by_key(|uniform| uniform.location);
+
+        info
+    }
+
+    /// Compare the enabled arrays of `vao` against the attributes this
+    /// program reads.
+    pub unsafe fn check_vertex_array(&self, vao: u32) -> Vec<LayoutMismatch> {
+        let mut max_attribs = 0;
+        gl::GetIntegerv(gl::MAX_VERTEX_ATTRIBS, &mut max_attribs);
+
+        let mut previous = 0;
+        gl::GetIntegerv(gl::VERTEX_ARRAY_BINDING, &mut previous);
+        gl::BindVertexArray(vao);
+
+        let mut mismatches = Vec::new();
+        for location in 0..max_attribs as u32 {
+            let mut enabled = 0;
+            gl::GetVertexAttribiv(location, gl::VERTEX_ATTRIB_ARRAY_ENABLED, &mut enabled);
+            // matrix attributes take one location per column
+            let attribute = self.attributes.iter().find(|attribute| {
+                attribute.location >= 0
+                    && (attribute.location as u32
+                        ..attribute.location as u32
+                            + columns(attribute.kind) * attribute.size as u32)
+                        .contains(&location)
+            });
+            match (attribute, enabled != 0) {
+                (Some(attribute), false) => mismatches.push(LayoutMismatch::Disabled {
+                    name: attribute.name.clone(),
+                    location,
+                }),
+                (None, true) => mismatches.push(LayoutMismatch::Unused { location }),
+                _ => {}
+            }
+        }
+
+        gl::BindVertexArray(previous as u32);
+        mismatches
+    }
+
+    /// Print a warning for every mismatch `check_vertex_array` finds.
+    pub unsafe fn warn_vertex_array(&self, vao: u32) {
+        for mismatch in self.check_vertex_array(vao) {
+            eprintln!("Warning: vertex array {}: {}", vao, mismatch);
+        }
+    }
+}
+
+impl fmt::Display for ProgramInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "  attributes:")?;
+        for attribute in &self.attributes {
+            writeln!(f, "    {}", attribute)?;
+        }
+        writeln!(f, "  uniforms:")?;
+        for uniform in &self.uniforms {
+            writeln!(f, "    {}", uniform)?;
+        }
+        writeln!(f, "  uniform blocks:")?;
+        for block in &self.blocks {
+            writeln!(
+                f,
+                "    {} (index {}, binding {}, {} bytes)",
+                block.name, block.index, block.binding, block.data_size
+            )?;
+            for uniform in &block.uniforms {
+                writeln!(f, "      {}", uniform)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Variable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.location >= 0 {
+            write!(f, "{:>3}  ", self.location)?;
+        } else {
+            write!(f, "  -  ")?;
+        }
+        write!(f, "{:<10} {}", type_name(self.kind), self.name)?;
+        if self.size > 1 {
+            write!(f, " ({} elements)", self.size)?;
+        }
+        Ok(())
+    }
+}
+
+/// The GLSL spelling of a GL type enum.
+pub fn type_name(kind: GLenum) -> String {
+    let name = match kind {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::UNSIGNED_INT_VEC4 => "uvec4",
+        gl::BOOL => "bool",
+        gl::BOOL_VEC2 => "bvec2",
+        gl::BOOL_VEC3 => "bvec3",
+        gl::BOOL_VEC4 => "bvec4",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::FLOAT_MAT2x3 => "mat2x3",
+        gl::FLOAT_MAT2x4 => "mat2x4",
+        gl::FLOAT_MAT3x2 => "mat3x2",
+        gl::FLOAT_MAT3x4 => "mat3x4",
+        gl::FLOAT_MAT4x2 => "mat4x2",
+        gl::FLOAT_MAT4x3 => "mat4x3",
+        gl::SAMPLER_1D => "sampler1D",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_3D => "sampler3D",
+        gl::SAMPLER_CUBE => "samplerCube",
+        gl::SAMPLER_2D_SHADOW => "sampler2DShadow",
+        gl::SAMPLER_2D_ARRAY => "sampler2DArray",
+        gl::SAMPLER_BUFFER => "samplerBuffer",
+        _ => return format!("0x{:04x}", kind),
+    };
+    name.to_string()
+}
+
+/// Number of attribute locations a single value of `kind` takes.
+fn columns(kind: GLenum) -> u32 {
+    match kind {
+        gl::FLOAT_MAT2 | gl::FLOAT_MAT2x3 | gl::FLOAT_MAT2x4 => 2,
+        gl::FLOAT_MAT3 | gl::FLOAT_MAT3x2 | gl::FLOAT_MAT3x4 => 3,
+        gl::FLOAT_MAT4 | gl::FLOAT_MAT4x2 | gl::FLOAT_MAT4x3 => 4,
+        _ => 1,
+    }
+}
+
+/// Call one of the `glGetActive*` functions, which all share a signature.
+unsafe fn active_variable<F>(max_length: GLint, get: F) -> Variable
+where
+    F: FnOnce(GLsizei, *mut GLsizei, *mut GLint, *mut GLenum, *mut GLchar),
+{
+    let mut name = vec![0u8; max_length.max(1) as usize];
+    let (mut length, mut size, mut kind) = (0, 0, 0);
+    get(
+        max_length,
+        &mut length,
+        &mut size,
+        &mut kind,
+        name.as_mut_ptr() as *mut GLchar,
+    );
+    name.truncate(length as usize);
+    Variable {
+        name: String::from_utf8_lossy(&name).into_owned(),
+        kind,
+        size,
+        location: -1,
+    }
+}
+
+fn c_name(name: &str) -> std::ffi::CString {
+    std::ffi::CString::new(name).expect("GL returned a name with a NUL byte")
+}
@@ -1,25 +1,14 @@
 #![allow(non_upper_case_globals)]
-extern crate glfw;
-use self::glfw::{Action, Context, Key};
-
+use crate::framework::{InitError, Tutorial};
+use crate::mesh::{AttributeArray, Command, Indices, Mesh, MeshData, NamedVertexArray};
 use crate::shader::Shader;
 
 extern crate gl;
 use self::gl::types::*;
 
 use cgmath::Matrix;
-use std::ffi::CString;
-use std::mem;
-use std::os::raw::c_void;
-use std::ptr;
-use std::str;
-use std::sync::mpsc::Receiver;
-
-// settings
-const SCR_WIDTH: u32 = 800;
-const SCR_HEIGHT: u32 = 600;
 
-const NUMBER_OF_VERTICES: i32 = 36;
+pub const NUMBER_OF_VERTICES: i32 = 36;
 
 const RIGHT_EXTENT: f32 = 0.8;
 const LEFT_EXTENT: f32 = -1.0 * RIGHT_EXTENT;
@@ -152,95 +141,134 @@ const index_data: [GLushort; 24] = [
 	17, 16, 14,
 ];
 
-#[allow(non_snake_case)]
-unsafe fn initialize_vertex_buffer() -> (u32, u32) {
-    let mut VBO = 0;
-    let mut index_buffer_object = 0;
-
-    // Initialize Buffer Object
-    gl::GenBuffers(1, &mut VBO);
-    gl::BindBuffer(gl::ARRAY_BUFFER, VBO);
-    gl::BufferData(
-        gl::ARRAY_BUFFER,
-        (vertices.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-        &vertices[0] as *const f32 as *const c_void,
-        gl::STATIC_DRAW,
-    );
-    gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-
-    // Initialize the index buffer
-    gl::GenBuffers(1, &mut index_buffer_object);
-    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer_object);
-    // Copy the index data over
-    gl::BufferData(
-        gl::ELEMENT_ARRAY_BUFFER,
-        (index_data.len() * mem::size_of::<GLushort>()) as GLsizeiptr,
-        &index_data[0] as *const GLushort as *const c_void,
-        gl::STATIC_DRAW,
-    );
-    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
+/// Both wedges in one mesh. They share the arrays: `render` draws the
+/// first one, and the second one's vertices are the second half of the
+/// arrays, which the "object2" vertex array starts at.
+pub fn wedges() -> MeshData {
+    let positions = NUMBER_OF_VERTICES as usize * 3;
+    MeshData {
+        attributes: vec![
+            AttributeArray::new(0, 3, vertices[..positions].to_vec()),
+            AttributeArray::new(1, 4, vertices[positions..].to_vec()),
+        ],
+        vertex_arrays: vec![NamedVertexArray {
+            name: "object2".to_string(),
+            locations: vec![0, 1],
+            first_vertex: NUMBER_OF_VERTICES as usize / 2,
+        }],
+        commands: vec![Command::Elements {
+            primitive: gl::TRIANGLES,
+            indices: Indices::U16(index_data.to_vec()),
+            restart: None,
+        }],
+        sub_meshes: Vec::new(),
+    }
+}
 
-    (VBO, index_buffer_object)
+/// The wedges without the vertex array for the second one. From ch_5_2 on
+/// the second wedge is drawn with a base vertex instead.
+pub fn base_vertex_wedges() -> MeshData {
+    MeshData {
+        vertex_arrays: Vec::new(),
+        ..wedges()
+    }
 }
 
-unsafe fn initialize_vertex_array_objects(VBO: u32, index_buffer_object: u32) -> (u32, u32) {
-    let mut VAO_object_1 = 0;
-    let mut VAO_object_2 = 0;
-
-    gl::GenVertexArrays(1, &mut VAO_object_1);
-    gl::BindVertexArray(VAO_object_1);
-
-    let mut color_data_offset = mem::size_of::<f32>() * 3 * NUMBER_OF_VERTICES as usize;
-
-    gl::BindBuffer(gl::ARRAY_BUFFER, VBO);
-    gl::EnableVertexAttribArray(0);
-    gl::EnableVertexAttribArray(1);
-    gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, 0, 0 as *const c_void);
-    gl::VertexAttribPointer(
-        1,
-        4,
-        gl::FLOAT,
-        gl::FALSE,
-        0,
-        color_data_offset as *const c_void,
-    );
-    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer_object);
+/// What the chapter 5 demos share: `standard.vs` with this chapter's
+/// frustum, a mesh of `data`, back face culling and, with `depth_test`, the
+/// depth buffer setup of ch_5_3. The chapters bring their own `display`.
+pub struct WedgeScene {
+    pub shader: Shader,
+    pub mesh: Mesh,
+    depth_test: bool,
+    frustum_scale: f32,
+    perspective_matrix: cgmath::Matrix4<f32>,
+}
 
-    gl::BindVertexArray(0);
+impl WedgeScene {
+    pub fn new(data: &MeshData, depth_test: bool) -> Result<WedgeScene, InitError> {
+        let shader = Shader::new(
+            "./src/section_ii/shaders/standard.vs",
+            "./src/section_ii/shaders/standard.fs",
+        )?;
 
-    gl::GenVertexArrays(1, &mut VAO_object_2);
-    gl::BindVertexArray(VAO_object_2);
+        let frustum_scale = 1.0f32;
+        let z_near = 0.5f32;
+        let z_far = 3.0f32;
 
-    let pos_data_offset = mem::size_of::<f32>() * 3 * (NUMBER_OF_VERTICES >> 1) as usize;
-    color_data_offset += mem::size_of::<f32>() * 4 * (NUMBER_OF_VERTICES >> 1) as usize;
+        let matrix = perspective_matrix(frustum_scale, z_near, z_far);
 
-    gl::EnableVertexAttribArray(0);
-    gl::EnableVertexAttribArray(1);
+        let mesh = Mesh::new(data)?;
 
-    gl::VertexAttribPointer(
-        0,
-        3,
-        gl::FLOAT,
-        gl::FALSE,
-        0,
-        pos_data_offset as *const c_void,
-    );
-    gl::VertexAttribPointer(
-        1,
-        4,
-        gl::FLOAT,
-        gl::FALSE,
-        0,
-        color_data_offset as *const c_void,
-    );
-    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer_object);
+        unsafe {
+            for vao in mesh.vertex_arrays() {
+                shader.info().warn_vertex_array(vao.id());
+            }
 
-    gl::BindVertexArray(0);
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::BACK);
+            gl::FrontFace(gl::CW);
 
-    (VAO_object_1, VAO_object_2)
+            if depth_test {
+                gl::Enable(gl::DEPTH_TEST);
+                gl::DepthMask(gl::TRUE);
+                gl::DepthFunc(gl::LEQUAL);
+                gl::DepthRange(0.0, 1.0);
+            }
+        }
+
+        unsafe {
+            let _program = shader.bind();
+            shader.set_uniform("perspectiveMatrix", matrix);
+        }
+
+        Ok(WedgeScene {
+            shader,
+            mesh,
+            depth_test,
+            frustum_scale,
+            perspective_matrix: matrix,
+        })
+    }
+
+    pub fn reshape(&mut self, width: i32, height: i32) {
+        unsafe {
+            reshape(
+                width,
+                height,
+                self.frustum_scale,
+                &mut self.perspective_matrix,
+                &self.shader,
+            )
+        }
+    }
+
+    pub fn teardown(&mut self) {
+        unsafe {
+            gl::Disable(gl::CULL_FACE);
+            gl::FrontFace(gl::CCW);
+            if self.depth_test {
+                gl::Disable(gl::DEPTH_TEST);
+                gl::DepthFunc(gl::LESS);
+            }
+        }
+    }
 }
 
-unsafe fn reshape(
+/// The perspective matrix for a square viewport; `reshape` keeps it in
+/// step with the window's aspect ratio.
+pub fn perspective_matrix(frustum_scale: f32, z_near: f32, z_far: f32) -> cgmath::Matrix4<f32> {
+    #[rustfmt::skip]
+    let matrix = cgmath::Matrix4::new(
+        frustum_scale, 0.0,           0.0,                               0.0,
+        0.0,           frustum_scale, 0.0,                               0.0,
+        0.0,           0.0,           (z_far + z_near)/(z_near - z_far), 2.0 * z_far * z_near / (z_near - z_far),
+        0.0,           0.0,                                                    -1.0, 0.0
+    );
+    matrix.transpose() // Transpose because I wrote it in row major order, but it should be column major order
+}
+
+pub unsafe fn reshape(
     width: i32,
     height: i32,
     frustum_scale: f32,
@@ -249,157 +277,50 @@ unsafe fn reshape(
 ) {
     perspective_matrix.x[0] = frustum_scale / (width as f32 / height as f32);
     perspective_matrix.y[1] = frustum_scale;
-    gl::UseProgram(program.ID);
-    program.setMat4(
-        &CString::new("perspectiveMatrix").unwrap(),
-        &perspective_matrix,
-    );
-    gl::UseProgram(0);
+    {
+        let _program = program.bind();
+        program.set_uniform("perspectiveMatrix", *perspective_matrix);
+    }
     gl::Viewport(0, 0, width, height);
 }
 
-unsafe fn display(shader: &Shader, VAO_1: u32, VAO_2: u32) {
+unsafe fn display(shader: &Shader, mesh: &Mesh) {
     gl::ClearColor(0.2, 0.3, 0.3, 1.0);
     gl::Clear(gl::COLOR_BUFFER_BIT);
 
-    gl::UseProgram(shader.ID);
+    let _program = shader.bind();
 
-    gl::BindVertexArray(VAO_1);
-    shader.setVec3(&CString::new("offset").unwrap(), 0.0, 0.0, 0.0);
-    gl::DrawElements(
-        gl::TRIANGLES,
-        index_data.len() as i32,
-        gl::UNSIGNED_SHORT,
-        0 as *const c_void,
-    );
-
-    gl::BindVertexArray(VAO_2);
-    shader.setVec3(&CString::new("offset").unwrap(), 0.0, 0.0, -1.0);
-    gl::DrawElements(
-        gl::TRIANGLES,
-        index_data.len() as i32,
-        gl::UNSIGNED_SHORT,
-        0 as *const c_void,
-    );
+    shader.set_uniform("offset", (0.0, 0.0, 0.0));
+    mesh.render();
 
-    gl::BindVertexArray(0);
-    gl::UseProgram(0);
+    shader.set_uniform("offset", (0.0, 0.0, -1.0));
+    mesh.render_named("object2");
 }
 
-#[allow(non_snake_case)]
-pub fn main() {
-    // glfw: initialize and configure
-    // ------------------------------
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-        glfw::OpenGlProfileHint::Core,
-    ));
-    #[cfg(target_os = "macos")]
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-
-    // glfw window creation
-    // --------------------
-    let (mut window, events) = glfw
-        .create_window(
-            SCR_WIDTH,
-            SCR_HEIGHT,
-            "LearnOpenGL",
-            glfw::WindowMode::Windowed,
-        )
-        .expect("Failed to create GLFW window");
-
-    window.make_current();
-    window.set_key_polling(true);
-    window.set_framebuffer_size_polling(true);
-
-    // gl: load all OpenGL function pointers
-    // ---------------------------------------
-    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
-
-    let shader = Shader::new(
-        "./src/section_ii/shaders/standard.vs",
-        "./src/section_ii/shaders/standard.fs",
-    );
-
-    let frustum_scale = 1.0f32;
-    let z_near = 0.5f32;
-    let z_far = 3.0f32;
-
-    // Setup Perspective Matrix
-    #[rustfmt::skip]
-    let mut matrix = cgmath::Matrix4::new(
-        frustum_scale, 0.0,           0.0,                               0.0,
-        0.0,           frustum_scale, 0.0,                               0.0,
-        0.0,           0.0,           (z_far + z_near)/(z_near - z_far), 2.0 * z_far * z_near / (z_near - z_far),
-        0.0,           0.0,                                                    -1.0, 0.0
-    ).transpose(); // Transpose because I wrote it in row major order, but it should be column major order
-
-    let (shaderProgram, VAO_1, VAO_2, VBO) = unsafe {
-        // link shaders
-        let shaderProgram = shader.ID;
-
-        let (VBO, index_buffer_object) = initialize_vertex_buffer();
-        let (VAO_1, VAO_2) = initialize_vertex_array_objects(VBO, index_buffer_object);
-
-        gl::Enable(gl::CULL_FACE);
-        gl::CullFace(gl::BACK);
-        gl::FrontFace(gl::CW);
-
-        (shaderProgram, VAO_1, VAO_2, VBO)
-    };
-
-    unsafe {
-        gl::UseProgram(shaderProgram);
-        // shader.setFloat(&CString::new("frustumScale").unwrap(), 1.0);
-        // shader.setFloat(&CString::new("zNear").unwrap(), 1.0);
-        // shader.setFloat(&CString::new("zFar").unwrap(), 3.0);
+pub struct OverlapNoDepth {
+    scene: WedgeScene,
+}
 
-        shader.setMat4(&CString::new("perspectiveMatrix").unwrap(), &matrix);
-        gl::UseProgram(0);
+impl Tutorial for OverlapNoDepth {
+    fn init() -> Result<Self, InitError> {
+        Ok(OverlapNoDepth {
+            scene: WedgeScene::new(&wedges(), false)?,
+        })
     }
 
-    // render loop
-    // -----------
-    while !window.should_close() {
-        // events
-        // -----
-        process_events(&mut window, &events, &shader, frustum_scale, &mut matrix);
+    fn display(&mut self, _time: f64) {
+        unsafe { display(&self.scene.shader, &self.scene.mesh) }
+    }
 
-        // render
-        // ------
-        unsafe {
-            display(&shader, VAO_1, VAO_2);
-        }
+    fn reshape(&mut self, width: i32, height: i32) {
+        self.scene.reshape(width, height)
+    }
 
-        // glfw: swap buffers and poll IO events (keys pressed/released, mouse moved etc.)
-        // -------------------------------------------------------------------------------
-        window.swap_buffers();
-        glfw.poll_events();
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.scene.shader]
     }
-}
 
-// NOTE: not the same version as in common.rs!
-fn process_events(
-    window: &mut glfw::Window,
-    events: &Receiver<(f64, glfw::WindowEvent)>,
-    program: &Shader,
-    frustum_scale: f32,
-    perspective_matrix: &mut cgmath::Matrix4<f32>,
-) {
-    for (_, event) in glfw::flush_messages(events) {
-        match event {
-            glfw::WindowEvent::FramebufferSize(width, height) => {
-                // make sure the viewport matches the new window dimensions; note that width and
-                // height will be significantly larger than specified on retina displays.
-                unsafe { reshape(width, height, frustum_scale, perspective_matrix, program) }
-                // unsafe { gl::Viewport(width / 2, height / 2, width, height) }
-                // unsafe { gl::Viewport(0, 0, width, height / 2) }
-            }
-            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                window.set_should_close(true)
-            }
-            _ => {}
-        }
+    fn teardown(&mut self) {
+        self.scene.teardown()
     }
 }
@@ -0,0 +1,49 @@
+use super::ch_5_1::{base_vertex_wedges, WedgeScene, NUMBER_OF_VERTICES};
+use crate::framework::{InitError, Tutorial};
+use crate::mesh::Mesh;
+use crate::shader::Shader;
+
+extern crate gl;
+
+unsafe fn display(shader: &Shader, mesh: &Mesh) {
+    gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+    gl::Clear(gl::COLOR_BUFFER_BIT);
+
+    let _program = shader.bind();
+
+    shader.set_uniform("offset", (0.0, 0.0, 0.0));
+    mesh.render();
+
+    // Same vertex array, same indices: the base vertex moves them onto the
+    // second wedge's half of the arrays.
+    shader.set_uniform("offset", (0.0, 0.0, -1.0));
+    mesh.render_base_vertex(NUMBER_OF_VERTICES / 2);
+}
+
+pub struct BaseVertexOverlap {
+    scene: WedgeScene,
+}
+
+impl Tutorial for BaseVertexOverlap {
+    fn init() -> Result<Self, InitError> {
+        Ok(BaseVertexOverlap {
+            scene: WedgeScene::new(&base_vertex_wedges(), false)?,
+        })
+    }
+
+    fn display(&mut self, _time: f64) {
+        unsafe { display(&self.scene.shader, &self.scene.mesh) }
+    }
+
+    fn reshape(&mut self, width: i32, height: i32) {
+        self.scene.reshape(width, height)
+    }
+
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.scene.shader]
+    }
+
+    fn teardown(&mut self) {
+        self.scene.teardown()
+    }
+}
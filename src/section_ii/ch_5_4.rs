@@ -0,0 +1,66 @@
+use super::ch_5_1::{base_vertex_wedges, WedgeScene, NUMBER_OF_VERTICES};
+use crate::framework::{InitError, Tutorial};
+use crate::mesh::Mesh;
+use crate::shader::Shader;
+
+extern crate gl;
+
+use std::f64::consts::PI;
+
+/// Seconds for the wedges to come through the near plane and back.
+const LOOP_DURATION: f64 = 4.0;
+
+/// How much closer to the camera than in ch_5_3 the wedges are at `time`:
+/// up to 1.2, which takes the front of the first wedge past the near plane
+/// at z = -0.5 and most of the rest of it after.
+pub fn approach(time: f64) -> f32 {
+    let phase = (time % LOOP_DURATION) / LOOP_DURATION * 2.0 * PI;
+    (0.6 - 0.6 * phase.cos()) as f32
+}
+
+/// Both wedges, moved closer by `approach(time)`; ch_5_5 draws the same.
+pub unsafe fn display(shader: &Shader, mesh: &Mesh, time: f64) {
+    let approach = approach(time);
+
+    gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+    gl::ClearDepth(1.0);
+    gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+    let _program = shader.bind();
+
+    // Whatever of the first wedge is closer than the near plane is clipped
+    // off, leaving its insides open.
+    shader.set_uniform("offset", (0.0, 0.0, approach));
+    mesh.render();
+
+    shader.set_uniform("offset", (0.0, 0.0, approach - 1.0));
+    mesh.render_base_vertex(NUMBER_OF_VERTICES / 2);
+}
+
+pub struct VertexClipping {
+    scene: WedgeScene,
+}
+
+impl Tutorial for VertexClipping {
+    fn init() -> Result<Self, InitError> {
+        Ok(VertexClipping {
+            scene: WedgeScene::new(&base_vertex_wedges(), true)?,
+        })
+    }
+
+    fn display(&mut self, time: f64) {
+        unsafe { display(&self.scene.shader, &self.scene.mesh, time) }
+    }
+
+    fn reshape(&mut self, width: i32, height: i32) {
+        self.scene.reshape(width, height)
+    }
+
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.scene.shader]
+    }
+
+    fn teardown(&mut self) {
+        self.scene.teardown()
+    }
+}
@@ -0,0 +1,59 @@
+use super::ch_5_1::{base_vertex_wedges, WedgeScene};
+use super::ch_5_4::display;
+use crate::framework::{InitError, Tutorial};
+use crate::shader::Shader;
+
+extern crate gl;
+
+use glfw::{Action, Key};
+
+pub struct DepthClamping {
+    scene: WedgeScene,
+    /// Whether GL_DEPTH_CLAMP is on; Space switches it.
+    depth_clamping: bool,
+}
+
+impl Tutorial for DepthClamping {
+    fn init() -> Result<Self, InitError> {
+        let scene = WedgeScene::new(&base_vertex_wedges(), true)?;
+
+        // Instead of clipping what's in front of the near plane, draw it
+        // with the nearest depth there is.
+        unsafe { gl::Enable(gl::DEPTH_CLAMP) };
+
+        Ok(DepthClamping {
+            scene,
+            depth_clamping: true,
+        })
+    }
+
+    fn display(&mut self, time: f64) {
+        unsafe { display(&self.scene.shader, &self.scene.mesh, time) }
+    }
+
+    fn reshape(&mut self, width: i32, height: i32) {
+        self.scene.reshape(width, height)
+    }
+
+    fn keyboard(&mut self, key: Key, action: Action, _modifiers: glfw::Modifiers) {
+        if key == Key::Space && action == Action::Press {
+            self.depth_clamping = !self.depth_clamping;
+            unsafe {
+                if self.depth_clamping {
+                    gl::Enable(gl::DEPTH_CLAMP);
+                } else {
+                    gl::Disable(gl::DEPTH_CLAMP);
+                }
+            }
+        }
+    }
+
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.scene.shader]
+    }
+
+    fn teardown(&mut self) {
+        self.scene.teardown();
+        unsafe { gl::Disable(gl::DEPTH_CLAMP) };
+    }
+}
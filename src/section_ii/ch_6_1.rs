@@ -0,0 +1,164 @@
+use super::ch_5_1::{perspective_matrix, reshape};
+use crate::framework::{InitError, Tutorial};
+use crate::loaders::{self, LoadError};
+use crate::mesh::Mesh;
+use crate::shader::Shader;
+
+extern crate gl;
+
+use cgmath::{Matrix4, Vector3};
+
+use std::f32::consts::PI;
+use std::path::Path;
+
+/// The frustum scale that gives a field of view of `fov_degrees`.
+pub fn frustum_scale(fov_degrees: f32) -> f32 {
+    let fov_radians = fov_degrees * PI / 180.0;
+    1.0 / (fov_radians / 2.0).tan()
+}
+
+/// The colored cube every chapter 6 demo draws instances of.
+pub fn cube() -> Result<Mesh, LoadError> {
+    loaders::load_mesh(Path::new("./src/meshes/UnitCubeColor.xml"))
+}
+
+/// The shader, cube and projection the chapter 6 demos share: everything
+/// but how they move their instances.
+pub struct Scene {
+    pub shader: Shader,
+    pub mesh: Mesh,
+    frustum_scale: f32,
+    perspective_matrix: cgmath::Matrix4<f32>,
+}
+
+impl Scene {
+    pub fn new() -> Result<Scene, InitError> {
+        let shader = Shader::new(
+            "./src/section_ii/shaders/local_transform.vs",
+            "./src/section_ii/shaders/standard.fs",
+        )?;
+
+        let frustum_scale = frustum_scale(45.0);
+        let z_near = 1.0f32;
+        let z_far = 45.0f32;
+
+        let matrix = perspective_matrix(frustum_scale, z_near, z_far);
+        let mesh = cube()?;
+
+        unsafe {
+            shader.info().warn_vertex_array(mesh.vertex_array().id());
+
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::BACK);
+            gl::FrontFace(gl::CW);
+
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::TRUE);
+            gl::DepthFunc(gl::LEQUAL);
+            gl::DepthRange(0.0, 1.0);
+        }
+
+        unsafe {
+            let _program = shader.bind();
+            shader.set_uniform("perspectiveMatrix", matrix);
+        }
+
+        Ok(Scene {
+            shader,
+            mesh,
+            frustum_scale,
+            perspective_matrix: matrix,
+        })
+    }
+
+    /// Draw the cube once for every model-to-camera matrix.
+    pub fn display(&self, instances: impl Iterator<Item = Matrix4<f32>>) {
+        unsafe {
+            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+            gl::ClearDepth(1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            let _program = self.shader.bind();
+            for model_to_camera in instances {
+                self.shader
+                    .set_uniform("modelToCameraMatrix", model_to_camera);
+                self.mesh.render();
+            }
+        }
+    }
+
+    pub fn reshape(&mut self, width: i32, height: i32) {
+        unsafe {
+            reshape(
+                width,
+                height,
+                self.frustum_scale,
+                &mut self.perspective_matrix,
+                &self.shader,
+            )
+        }
+    }
+
+    pub fn teardown(&mut self) {
+        unsafe {
+            gl::Disable(gl::CULL_FACE);
+            gl::FrontFace(gl::CCW);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthFunc(gl::LESS);
+        }
+    }
+}
+
+/// Where an instance is at `time`, in camera space.
+type Path3 = fn(f32) -> Vector3<f32>;
+
+fn stationary(_time: f32) -> Vector3<f32> {
+    Vector3::new(0.0, 0.0, -10.0)
+}
+
+fn oval(time: f32) -> Vector3<f32> {
+    let loop_duration = 3.0;
+    let angle = (time % loop_duration) / loop_duration * 2.0 * PI;
+    Vector3::new(angle.cos() * 2.0, angle.sin() * 3.0, -10.0)
+}
+
+fn bottom_circle(time: f32) -> Vector3<f32> {
+    let loop_duration = 12.0;
+    let angle = (time % loop_duration) / loop_duration * 2.0 * PI;
+    Vector3::new(angle.cos() * 2.5, -1.75, angle.sin() * 2.5 - 10.0)
+}
+
+const INSTANCES: &[Path3] = &[stationary, oval, bottom_circle];
+
+pub struct Translation {
+    scene: Scene,
+}
+
+impl Tutorial for Translation {
+    fn init() -> Result<Self, InitError> {
+        Ok(Translation {
+            scene: Scene::new()?,
+        })
+    }
+
+    fn display(&mut self, time: f64) {
+        let time = time as f32;
+        self.scene.display(
+            INSTANCES
+                .iter()
+                .map(|offset| Matrix4::from_translation(offset(time))),
+        );
+    }
+
+    fn reshape(&mut self, width: i32, height: i32) {
+        self.scene.reshape(width, height)
+    }
+
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.scene.shader]
+    }
+
+    fn teardown(&mut self) {
+        self.scene.teardown()
+    }
+}
@@ -0,0 +1,86 @@
+use super::ch_6_1::Scene;
+use crate::framework::{InitError, Tutorial};
+use crate::shader::Shader;
+
+use cgmath::{Matrix4, Vector3};
+
+/// Goes from 0 to 1 and back again every `loop_duration` seconds.
+fn lerp_factor(time: f32, loop_duration: f32) -> f32 {
+    let value = (time % loop_duration) / loop_duration;
+    let value = if value > 0.5 { 1.0 - value } else { value };
+    value * 2.0
+}
+
+fn mix(x: f32, y: f32, a: f32) -> f32 {
+    x + (y - x) * a
+}
+
+/// How an instance is stretched at `time`, along each axis.
+type Scaling = fn(f32) -> Vector3<f32>;
+
+fn null_scale(_time: f32) -> Vector3<f32> {
+    Vector3::new(1.0, 1.0, 1.0)
+}
+
+fn static_uniform_scale(_time: f32) -> Vector3<f32> {
+    Vector3::new(4.0, 4.0, 4.0)
+}
+
+fn static_non_uniform_scale(_time: f32) -> Vector3<f32> {
+    Vector3::new(0.5, 1.0, 10.0)
+}
+
+fn dynamic_uniform_scale(time: f32) -> Vector3<f32> {
+    let scale = mix(1.0, 4.0, lerp_factor(time, 3.0));
+    Vector3::new(scale, scale, scale)
+}
+
+fn dynamic_non_uniform_scale(time: f32) -> Vector3<f32> {
+    Vector3::new(
+        mix(1.0, 0.5, lerp_factor(time, 3.0)),
+        1.0,
+        mix(1.0, 10.0, lerp_factor(time, 5.0)),
+    )
+}
+
+const INSTANCES: &[(Scaling, [f32; 3])] = &[
+    (null_scale, [0.0, 0.0, -22.5]),
+    (static_uniform_scale, [-5.0, -5.0, -22.5]),
+    (static_non_uniform_scale, [-5.0, 5.0, -22.5]),
+    (dynamic_uniform_scale, [5.0, 5.0, -22.5]),
+    (dynamic_non_uniform_scale, [5.0, -5.0, -22.5]),
+];
+
+pub struct Scale {
+    scene: Scene,
+}
+
+impl Tutorial for Scale {
+    fn init() -> Result<Self, InitError> {
+        Ok(Scale {
+            scene: Scene::new()?,
+        })
+    }
+
+    fn display(&mut self, time: f64) {
+        let time = time as f32;
+        self.scene
+            .display(INSTANCES.iter().map(|&(scaling, offset)| {
+                let scale = scaling(time);
+                Matrix4::from_translation(offset.into())
+                    * Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z)
+            }));
+    }
+
+    fn reshape(&mut self, width: i32, height: i32) {
+        self.scene.reshape(width, height)
+    }
+
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.scene.shader]
+    }
+
+    fn teardown(&mut self) {
+        self.scene.teardown()
+    }
+}
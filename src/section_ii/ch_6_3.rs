@@ -0,0 +1,76 @@
+use super::ch_6_1::Scene;
+use crate::framework::{InitError, Tutorial};
+use crate::shader::Shader;
+
+use cgmath::{InnerSpace, Matrix3, Matrix4, Rad, SquareMatrix, Vector3};
+
+use std::f32::consts::PI;
+
+/// One full turn every `loop_duration` seconds.
+fn angle(time: f32, loop_duration: f32) -> Rad<f32> {
+    Rad((time % loop_duration) / loop_duration * 2.0 * PI)
+}
+
+/// How an instance is turned at `time`.
+type Orientation = fn(f32) -> Matrix3<f32>;
+
+fn null_rotation(_time: f32) -> Matrix3<f32> {
+    Matrix3::identity()
+}
+
+fn rotate_x(time: f32) -> Matrix3<f32> {
+    Matrix3::from_angle_x(angle(time, 3.0))
+}
+
+fn rotate_y(time: f32) -> Matrix3<f32> {
+    Matrix3::from_angle_y(angle(time, 2.0))
+}
+
+fn rotate_z(time: f32) -> Matrix3<f32> {
+    Matrix3::from_angle_z(angle(time, 2.0))
+}
+
+fn rotate_axis(time: f32) -> Matrix3<f32> {
+    let axis = Vector3::new(1.0, 1.0, 1.0).normalize();
+    Matrix3::from_axis_angle(axis, angle(time, 2.0))
+}
+
+const INSTANCES: &[(Orientation, [f32; 3])] = &[
+    (null_rotation, [0.0, 0.0, -12.5]),
+    (rotate_x, [-2.5, -2.5, -12.5]),
+    (rotate_y, [-2.5, 2.5, -12.5]),
+    (rotate_z, [2.5, 2.5, -12.5]),
+    (rotate_axis, [2.5, -2.5, -12.5]),
+];
+
+pub struct Rotation {
+    scene: Scene,
+}
+
+impl Tutorial for Rotation {
+    fn init() -> Result<Self, InitError> {
+        Ok(Rotation {
+            scene: Scene::new()?,
+        })
+    }
+
+    fn display(&mut self, time: f64) {
+        let time = time as f32;
+        self.scene
+            .display(INSTANCES.iter().map(|&(orientation, offset)| {
+                Matrix4::from_translation(offset.into()) * Matrix4::from(orientation(time))
+            }));
+    }
+
+    fn reshape(&mut self, width: i32, height: i32) {
+        self.scene.reshape(width, height)
+    }
+
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.scene.shader]
+    }
+
+    fn teardown(&mut self) {
+        self.scene.teardown()
+    }
+}
@@ -1 +1,8 @@
 pub mod ch_5_1;
+pub mod ch_5_2;
+pub mod ch_5_3;
+pub mod ch_5_4;
+pub mod ch_5_5;
+pub mod ch_6_1;
+pub mod ch_6_2;
+pub mod ch_6_3;
@@ -0,0 +1,14 @@
+#version 330
+
+#include "../../shaders/vertex_color.glsl"
+#include "../../shaders/perspective.glsl"
+
+uniform mat4 modelToCameraMatrix;
+
+void main()
+{
+	vec4 cameraPos = modelToCameraMatrix * position;
+
+	gl_Position = perspective(cameraPos);
+	theColor = color;
+}
@@ -1,225 +1,568 @@
 #![allow(non_snake_case)]
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
 use std::ffi::{CStr, CString};
-use std::fs::File;
-use std::io::Read;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::ptr;
-use std::str;
+use std::time::SystemTime;
 
-use gl;
 use gl::types::*;
 
-use cgmath::prelude::*;
-use cgmath::{Matrix, Matrix4, Vector3};
+use cgmath::{Matrix4, Vector3};
+
+use crate::objects::{Program, ProgramBinding};
+use crate::preprocessor::{self, Preprocessed};
+use crate::reflection::ProgramInfo;
+use crate::uniform::{Uniform, UniformValue};
 
 pub struct Shader {
-    pub ID: u32,
+    program: Program,
+    sources: Vec<Source>,
+    /// The last value set for every uniform, so they can be put back into
+    /// the new program after a reload.
+    uniforms: RefCell<HashMap<String, Uniform>>,
+    /// Uniform locations already looked up in the current program. Names the
+    /// program doesn't have are cached as -1 so they are only reported once.
+    locations: RefCell<HashMap<String, GLint>>,
+    info: ProgramInfo,
+    bindings: Bindings,
+}
+
+/// A stage of the program and the file it is built from.
+struct Source {
+    kind: GLenum,
+    stage: &'static str,
+    /// The file name, or a description of the source for inline code.
+    path: String,
+    /// The source itself when it isn't read from `path`.
+    code: Option<String>,
+    /// `#define`s inserted after the `#version` line.
+    defines: Vec<(String, String)>,
+    /// The file and everything it includes, with their mtimes when they were
+    /// last compiled.
+    dependencies: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Source {
+    fn new(kind: GLenum, stage: &'static str, source: ShaderSource) -> Source {
+        let (path, code, dependencies) = match source {
+            ShaderSource::File(path) => {
+                let mtime = modified(&path);
+                (path.display().to_string(), None, vec![(path, mtime)])
+            }
+            ShaderSource::Code(code) => {
+                (format!("<inline {} shader>", stage), Some(code), Vec::new())
+            }
+        };
+        Source {
+            kind,
+            stage,
+            path,
+            code,
+            defines: Vec::new(),
+            dependencies,
+        }
+    }
+
+    fn changed(&self) -> bool {
+        self.dependencies
+            .iter()
+            .any(|(path, mtime)| modified(path) != *mtime)
+    }
+}
+
+/// Why a shader program could not be built.
+#[derive(Debug)]
+pub enum ShaderError {
+    /// The source file could not be read.
+    Io { path: String, source: io::Error },
+    /// A single stage failed to compile. `log` is the driver's full info log.
+    Compile {
+        stage: &'static str,
+        path: String,
+        log: String,
+    },
+    /// The stages compiled but could not be linked together.
+    Link { paths: Vec<String>, log: String },
+    /// An `#include` couldn't be resolved.
+    Preprocess {
+        path: String,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderError::Io { path, source } => write!(f, "Failed to read {}: {}", path, source),
+            ShaderError::Compile { stage, path, log } => write!(
+                f,
+                "Failed to compile {} shader {}:\n{}",
+                stage,
+                path,
+                log.trim_end()
+            ),
+            ShaderError::Link { paths, log } => write!(
+                f,
+                "Failed to link program ({}):\n{}",
+                paths.join(", "),
+                log.trim_end()
+            ),
+            ShaderError::Preprocess {
+                path,
+                line,
+                message,
+            } => write!(f, "Failed to preprocess {}:{}: {}", path, line, message),
+        }
+    }
+}
+
+impl Error for ShaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ShaderError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Where the code of a stage comes from. Strings and paths convert to
+/// `File`, so `builder.vertex("shader.vs")` reads a file.
+pub enum ShaderSource {
+    File(PathBuf),
+    Code(String),
+}
+
+impl ShaderSource {
+    /// GLSL source held in memory rather than a file.
+    pub fn code(code: &str) -> ShaderSource {
+        ShaderSource::Code(code.to_string())
+    }
+}
+
+impl From<&str> for ShaderSource {
+    fn from(path: &str) -> ShaderSource {
+        ShaderSource::File(PathBuf::from(path))
+    }
+}
+
+impl From<&Path> for ShaderSource {
+    fn from(path: &Path) -> ShaderSource {
+        ShaderSource::File(path.to_path_buf())
+    }
+}
+
+impl From<PathBuf> for ShaderSource {
+    fn from(path: PathBuf) -> ShaderSource {
+        ShaderSource::File(path)
+    }
+}
+
+/// Locations assigned with `glBindAttribLocation`/`glBindFragDataLocation`
+/// before linking, for shaders that don't use `layout(location = N)`.
+#[derive(Clone, Default)]
+struct Bindings {
+    attributes: Vec<(u32, CString)>,
+    frag_data: Vec<(u32, CString)>,
+}
+
+/// Collects the stages of a program and how to build them.
+///
+/// ```ignore
+/// let shader = ShaderBuilder::new()
+///     .vertex("./src/ch_2/shaders/identity.vs")
+///     .fragment(ShaderSource::code(FRAGMENT_SHADER))
+///     .bind_frag_data(0, "outputColor")
+///     .build()?;
+/// ```
+#[derive(Default)]
+pub struct ShaderBuilder {
+    sources: Vec<Source>,
+    defines: Vec<(String, String)>,
+    bindings: Bindings,
+}
+
+#[allow(dead_code)]
+impl ShaderBuilder {
+    pub fn new() -> ShaderBuilder {
+        ShaderBuilder::default()
+    }
+
+    pub fn vertex<S: Into<ShaderSource>>(self, source: S) -> ShaderBuilder {
+        self.stage(gl::VERTEX_SHADER, "vertex", source.into())
+    }
+
+    pub fn fragment<S: Into<ShaderSource>>(self, source: S) -> ShaderBuilder {
+        self.stage(gl::FRAGMENT_SHADER, "fragment", source.into())
+    }
+
+    pub fn geometry<S: Into<ShaderSource>>(self, source: S) -> ShaderBuilder {
+        self.stage(gl::GEOMETRY_SHADER, "geometry", source.into())
+    }
+
+    pub fn tess_control<S: Into<ShaderSource>>(self, source: S) -> ShaderBuilder {
+        self.stage(
+            gl::TESS_CONTROL_SHADER,
+            "tessellation control",
+            source.into(),
+        )
+    }
+
+    pub fn tess_eval<S: Into<ShaderSource>>(self, source: S) -> ShaderBuilder {
+        self.stage(
+            gl::TESS_EVALUATION_SHADER,
+            "tessellation evaluation",
+            source.into(),
+        )
+    }
+
+    fn stage(mut self, kind: GLenum, stage: &'static str, source: ShaderSource) -> ShaderBuilder {
+        self.sources.push(Source::new(kind, stage, source));
+        self
+    }
+
+    /// Add `#define name value` to every stage, right after `#version`.
+    pub fn define(mut self, name: &str, value: &str) -> ShaderBuilder {
+        self.defines.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Bind the vertex attribute `name` to `index` before linking.
+    pub fn bind_attribute(mut self, index: u32, name: &str) -> ShaderBuilder {
+        let name = CString::new(name).expect("Attribute name contains a NUL byte");
+        self.bindings.attributes.push((index, name));
+        self
+    }
+
+    /// Bind the fragment shader output `name` to color number `color` before
+    /// linking.
+    pub fn bind_frag_data(mut self, color: u32, name: &str) -> ShaderBuilder {
+        let name = CString::new(name).expect("Output name contains a NUL byte");
+        self.bindings.frag_data.push((color, name));
+        self
+    }
+
+    /// Compile and link the program. Needs a current GL context.
+    pub fn build(mut self) -> Result<Shader, ShaderError> {
+        for source in &mut self.sources {
+            source.defines = self.defines.clone();
+        }
+        let program = Program::from_raw(unsafe { build(&mut self.sources, &self.bindings)? });
+        Ok(Shader {
+            info: unsafe { ProgramInfo::query(program.id()) },
+            program,
+            sources: self.sources,
+            uniforms: RefCell::new(HashMap::new()),
+            locations: RefCell::new(HashMap::new()),
+            bindings: self.bindings,
+        })
+    }
 }
 
 /// NOTE: mixture of `shader_s.h` and `shader_m.h` (the latter just contains
 /// a few more setters for uniforms)
 #[allow(dead_code)]
 impl Shader {
-    pub fn new(vertexPath: &str, fragmentPath: &str) -> Shader {
-        let mut shader = Shader { ID: 0 };
-        // 1. retrieve the vertex/fragment source code from filesystem
-        let mut vShaderFile =
-            File::open(vertexPath).unwrap_or_else(|_| panic!("Failed to open {}", vertexPath));
-        let mut fShaderFile =
-            File::open(fragmentPath).unwrap_or_else(|_| panic!("Failed to open {}", fragmentPath));
-        let mut vertexCode = String::new();
-        let mut fragmentCode = String::new();
-        vShaderFile
-            .read_to_string(&mut vertexCode)
-            .expect("Failed to read vertex shader");
-        fShaderFile
-            .read_to_string(&mut fragmentCode)
-            .expect("Failed to read fragment shader");
-
-        let vShaderCode = CString::new(vertexCode.as_bytes()).unwrap();
-        let fShaderCode = CString::new(fragmentCode.as_bytes()).unwrap();
-
-        // 2. compile shaders
-        unsafe {
-            // vertex shader
-            let vertex = gl::CreateShader(gl::VERTEX_SHADER);
-            gl::ShaderSource(vertex, 1, &vShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(vertex);
-            shader.checkCompileErrors(vertex, "VERTEX");
-            // fragment Shader
-            let fragment = gl::CreateShader(gl::FRAGMENT_SHADER);
-            gl::ShaderSource(fragment, 1, &fShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(fragment);
-            shader.checkCompileErrors(fragment, "FRAGMENT");
-            // shader Program
-            let ID = gl::CreateProgram();
-            gl::AttachShader(ID, vertex);
-            gl::AttachShader(ID, fragment);
-            gl::LinkProgram(ID);
-            shader.checkCompileErrors(ID, "PROGRAM");
-            // delete the shaders as they're linked into our program now and no longer necessary
-            gl::DeleteShader(vertex);
-            gl::DeleteShader(fragment);
-            shader.ID = ID;
+    pub fn new(vertexPath: &str, fragmentPath: &str) -> Result<Shader, ShaderError> {
+        ShaderBuilder::new()
+            .vertex(vertexPath)
+            .fragment(fragmentPath)
+            .build()
+    }
+
+    /// Like `new`, with `#define NAME VALUE` lines added to both stages so one
+    /// pair of files can be built into several variants.
+    pub fn with_defines(
+        vertexPath: &str,
+        fragmentPath: &str,
+        defines: &[(&str, &str)],
+    ) -> Result<Shader, ShaderError> {
+        defines
+            .iter()
+            .fold(
+                ShaderBuilder::new()
+                    .vertex(vertexPath)
+                    .fragment(fragmentPath),
+                |builder, (name, value)| builder.define(name, value),
+            )
+            .build()
+    }
+
+    /// Rebuild the program if any of its source files, or the files they
+    /// include, changed on disk since they were last compiled.
+    pub fn reload_if_changed(&mut self) {
+        if self.sources.iter().any(Source::changed) {
+            self.reload();
+        }
+    }
+
+    /// Rebuild the program from its source files. If that fails the error is
+    /// printed and the previous program stays in use.
+    pub fn reload(&mut self) {
+        // Remember the new mtimes even if the build fails, so a broken file
+        // is only reported once rather than on every frame.
+        for source in &mut self.sources {
+            for (path, mtime) in &mut source.dependencies {
+                *mtime = modified(path);
+            }
+        }
+
+        match unsafe { build(&mut self.sources, &self.bindings) } {
+            Ok(program) => unsafe {
+                self.program = Program::from_raw(program);
+                self.info = ProgramInfo::query(program);
+                self.locations.borrow_mut().clear();
+                self.restore_uniforms();
+                println!("Reloaded {}", self.paths().join(", "));
+            },
+            Err(err) => eprintln!("{}\nKeeping the previous program", err),
+        }
+    }
+
+    /// The source files, in the order the stages were given.
+    pub fn paths(&self) -> Vec<&str> {
+        self.sources
+            .iter()
+            .map(|source| source.path.as_str())
+            .collect()
+    }
+
+    /// The active attributes, uniforms and uniform blocks of the program.
+    pub fn info(&self) -> &ProgramInfo {
+        &self.info
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Use the program until the returned guard is dropped.
+    pub fn bind(&self) -> ProgramBinding<'_> {
+        self.program.bind()
+    }
+
+    unsafe fn restore_uniforms(&self) {
+        let _program = self.bind();
+        for (name, value) in self.uniforms.borrow().iter() {
+            value.apply(self.location(name));
+        }
+    }
+
+    /// Set a uniform on the program, which has to be in use, and remember it
+    /// for reloads. A name the program has no active uniform for is reported
+    /// on stderr the first time and otherwise ignored, like GL does.
+    pub unsafe fn set_uniform<T: UniformValue>(&self, name: &str, value: T) {
+        let value = value.to_uniform();
+        value.apply(self.location(name));
+
+        let mut uniforms = self.uniforms.borrow_mut();
+        match uniforms.get_mut(name) {
+            Some(previous) => *previous = value,
+            None => {
+                uniforms.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    /// Location of `name` in the current program, looked up only once.
+    unsafe fn location(&self, name: &str) -> GLint {
+        if let Some(location) = self.locations.borrow().get(name) {
+            return *location;
         }
 
-        shader
+        let c_name = CString::new(name).expect("Uniform name contains a NUL byte");
+        let location = gl::GetUniformLocation(self.program.id(), c_name.as_ptr());
+        if location == -1 {
+            eprintln!(
+                "Warning: {} has no active uniform `{}`",
+                self.paths().join(", "),
+                name
+            );
+        }
+        self.locations
+            .borrow_mut()
+            .insert(name.to_string(), location);
+        location
     }
 
     /// activate the shader
     /// ------------------------------------------------------------------------
     pub unsafe fn useProgram(&self) {
-        gl::UseProgram(self.ID)
+        gl::UseProgram(self.program.id())
     }
 
-    /// utility uniform functions
+    /// utility uniform functions, kept for code ported from LearnOpenGL;
+    /// prefer `set_uniform`
     /// ------------------------------------------------------------------------
     pub unsafe fn setBool(&self, name: &CStr, value: bool) {
-        gl::Uniform1i(gl::GetUniformLocation(self.ID, name.as_ptr()), value as i32);
+        self.set_uniform(&name.to_string_lossy(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setInt(&self, name: &CStr, value: i32) {
-        gl::Uniform1i(gl::GetUniformLocation(self.ID, name.as_ptr()), value);
+        self.set_uniform(&name.to_string_lossy(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setFloat(&self, name: &CStr, value: f32) {
-        gl::Uniform1f(gl::GetUniformLocation(self.ID, name.as_ptr()), value);
+        self.set_uniform(&name.to_string_lossy(), value);
     }
     pub unsafe fn set2F(&self, name: &CStr, value: (f32, f32)) {
-        gl::Uniform2f(
-            gl::GetUniformLocation(self.ID, name.as_ptr()),
-            value.0,
-            value.1,
-        );
+        self.set_uniform(&name.to_string_lossy(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setVector3(&self, name: &CStr, value: &Vector3<f32>) {
-        gl::Uniform3fv(
-            gl::GetUniformLocation(self.ID, name.as_ptr()),
-            1,
-            value.as_ptr(),
-        );
+        self.set_uniform(&name.to_string_lossy(), value);
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setVec3(&self, name: &CStr, x: f32, y: f32, z: f32) {
-        gl::Uniform3f(gl::GetUniformLocation(self.ID, name.as_ptr()), x, y, z);
+        self.set_uniform(&name.to_string_lossy(), (x, y, z));
     }
     /// ------------------------------------------------------------------------
     pub unsafe fn setMat4(&self, name: &CStr, mat: &Matrix4<f32>) {
-        gl::UniformMatrix4fv(
-            gl::GetUniformLocation(self.ID, name.as_ptr()),
-            1,
-            gl::FALSE,
-            mat.as_ptr(),
-        );
+        self.set_uniform(&name.to_string_lossy(), mat);
     }
 
-    /// utility function for checking shader compilation/linking errors.
-    /// ------------------------------------------------------------------------
-    unsafe fn checkCompileErrors(&self, shader: u32, type_: &str) {
-        let mut success = gl::FALSE as GLint;
-        let mut infoLog = Vec::with_capacity(1024);
-        infoLog.set_len(1024);
-        let parse_info_log = |info_log: Vec<u8>| {
-            let first_null_byte = info_log
-                .iter()
-                .position(|x| *x == 0)
-                .expect("Failed to find a null byte");
-            CString::new(&info_log[0..first_null_byte]).expect("Couldn't parse error string")
+    /// Only used in 4.9 Geometry shaders - ignore until then (shader.h in original C++)
+    pub fn with_geometry_shader(
+        vertexPath: &str,
+        fragmentPath: &str,
+        geometryPath: &str,
+    ) -> Result<Self, ShaderError> {
+        ShaderBuilder::new()
+            .vertex(vertexPath)
+            .fragment(fragmentPath)
+            .geometry(geometryPath)
+            .build()
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Read, preprocess, compile and link every stage into a new program.
+unsafe fn build(sources: &mut [Source], bindings: &Bindings) -> Result<u32, ShaderError> {
+    // 1. retrieve the source code from filesystem
+    let mut codes = Vec::with_capacity(sources.len());
+    for source in sources.iter_mut() {
+        let code = match &source.code {
+            Some(code) => preprocessor::preprocess_str(&source.path, code, &source.defines)?,
+            None => preprocessor::preprocess(Path::new(&source.path), &source.defines)?,
         };
-        if type_ != "PROGRAM" {
-            gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
-            if success != gl::TRUE as GLint {
-                gl::GetShaderInfoLog(
-                    shader,
-                    1024,
-                    ptr::null_mut(),
-                    infoLog.as_mut_ptr() as *mut GLchar,
-                );
-                println!(
-                    "ERROR::SHADER_COMPILATION_ERROR of type: {}\n{:?}\n \
-                          -- --------------------------------------------------- -- ",
-                    type_,
-                    parse_info_log(infoLog) // CString::new(infoLog) // str::from_utf8(&infoLog)
-                );
-            }
-        } else {
-            gl::GetProgramiv(shader, gl::LINK_STATUS, &mut success);
-            if success != gl::TRUE as GLint {
-                gl::GetProgramInfoLog(
-                    shader,
-                    1024,
-                    ptr::null_mut(),
-                    infoLog.as_mut_ptr() as *mut GLchar,
-                );
-                println!(
-                    "ERROR::PROGRAM_LINKING_ERROR of type: {}\n{:?}\n \
-                          -- --------------------------------------------------- -- ",
-                    type_,
-                    parse_info_log(infoLog) // CString::new(infoLog) // str::from_utf8(&infoLog)
-                );
+        // watch the includes too, even if this stage fails to compile below
+        let inline = source.code.is_some() as usize;
+        source.dependencies = code.files[inline..]
+            .iter()
+            .map(|path| (path.clone(), modified(path)))
+            .collect();
+        codes.push(code);
+    }
+
+    // 2. compile shaders
+    let mut shaders = Vec::with_capacity(sources.len());
+    for (source, code) in sources.iter().zip(&codes) {
+        match compile(source.kind, source.stage, &source.path, code) {
+            Ok(shader) => shaders.push(shader),
+            Err(err) => {
+                shaders.iter().for_each(|shader| gl::DeleteShader(*shader));
+                return Err(err);
             }
         }
     }
 
-    /// Only used in 4.9 Geometry shaders - ignore until then (shader.h in original C++)
-    pub fn with_geometry_shader(vertexPath: &str, fragmentPath: &str, geometryPath: &str) -> Self {
-        let mut shader = Shader { ID: 0 };
-        // 1. retrieve the vertex/fragment source code from filesystem
-        let mut vShaderFile =
-            File::open(vertexPath).unwrap_or_else(|_| panic!("Failed to open {}", vertexPath));
-        let mut fShaderFile =
-            File::open(fragmentPath).unwrap_or_else(|_| panic!("Failed to open {}", fragmentPath));
-        let mut gShaderFile =
-            File::open(geometryPath).unwrap_or_else(|_| panic!("Failed to open {}", geometryPath));
-        let mut vertexCode = String::new();
-        let mut fragmentCode = String::new();
-        let mut geometryCode = String::new();
-        vShaderFile
-            .read_to_string(&mut vertexCode)
-            .expect("Failed to read vertex shader");
-        fShaderFile
-            .read_to_string(&mut fragmentCode)
-            .expect("Failed to read fragment shader");
-        gShaderFile
-            .read_to_string(&mut geometryCode)
-            .expect("Failed to read geometry shader");
-
-        let vShaderCode = CString::new(vertexCode.as_bytes()).unwrap();
-        let fShaderCode = CString::new(fragmentCode.as_bytes()).unwrap();
-        let gShaderCode = CString::new(geometryCode.as_bytes()).unwrap();
-
-        // 2. compile shaders
-        unsafe {
-            // vertex shader
-            let vertex = gl::CreateShader(gl::VERTEX_SHADER);
-            gl::ShaderSource(vertex, 1, &vShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(vertex);
-            shader.checkCompileErrors(vertex, "VERTEX");
-            // fragment Shader
-            let fragment = gl::CreateShader(gl::FRAGMENT_SHADER);
-            gl::ShaderSource(fragment, 1, &fShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(fragment);
-            shader.checkCompileErrors(fragment, "FRAGMENT");
-            // geometry shader
-            let geometry = gl::CreateShader(gl::GEOMETRY_SHADER);
-            gl::ShaderSource(geometry, 1, &gShaderCode.as_ptr(), ptr::null());
-            gl::CompileShader(geometry);
-            shader.checkCompileErrors(geometry, "GEOMETRY");
-
-            // shader Program
-            let ID = gl::CreateProgram();
-            gl::AttachShader(ID, vertex);
-            gl::AttachShader(ID, fragment);
-            gl::AttachShader(ID, geometry);
-            gl::LinkProgram(ID);
-            shader.checkCompileErrors(ID, "PROGRAM");
-            // delete the shaders as they're linked into our program now and no longer necessary
-            gl::DeleteShader(vertex);
-            gl::DeleteShader(fragment);
-            gl::DeleteShader(geometry);
-            shader.ID = ID;
-        }
+    // shader Program
+    let paths: Vec<&str> = sources.iter().map(|source| source.path.as_str()).collect();
+    let program = link(&shaders, &paths, bindings);
+    // delete the shaders as they're linked into our program now and no longer necessary
+    shaders.iter().for_each(|shader| gl::DeleteShader(*shader));
+    program
+}
+
+/// Compile a single stage, handing back the shader object on success.
+unsafe fn compile(
+    kind: GLenum,
+    stage: &'static str,
+    path: &str,
+    source: &Preprocessed,
+) -> Result<u32, ShaderError> {
+    let code = CString::new(source.code.as_bytes()).map_err(|_| ShaderError::Io {
+        path: path.to_string(),
+        source: io::Error::new(io::ErrorKind::InvalidData, "source contains a NUL byte"),
+    })?;
+    let shader = gl::CreateShader(kind);
+    gl::ShaderSource(shader, 1, &code.as_ptr(), ptr::null());
+    gl::CompileShader(shader);
+
+    let mut success = gl::FALSE as GLint;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+    if success != gl::TRUE as GLint {
+        let mut len = 0;
+        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+        let mut infoLog = vec![0u8; len.max(1) as usize];
+        let mut written = 0;
+        gl::GetShaderInfoLog(
+            shader,
+            infoLog.len() as GLsizei,
+            &mut written,
+            infoLog.as_mut_ptr() as *mut GLchar,
+        );
+        infoLog.truncate(written as usize);
+        gl::DeleteShader(shader);
+        return Err(ShaderError::Compile {
+            stage,
+            path: path.to_string(),
+            log: source.map_log(&parse_info_log(infoLog)),
+        });
+    }
+    Ok(shader)
+}
+
+/// Link compiled stages into a program. The caller still owns the shaders.
+unsafe fn link(shaders: &[u32], paths: &[&str], bindings: &Bindings) -> Result<u32, ShaderError> {
+    let program = gl::CreateProgram();
+    for shader in shaders {
+        gl::AttachShader(program, *shader);
+    }
+    for (index, name) in &bindings.attributes {
+        gl::BindAttribLocation(program, *index, name.as_ptr());
+    }
+    for (color, name) in &bindings.frag_data {
+        gl::BindFragDataLocation(program, *color, name.as_ptr());
+    }
+    gl::LinkProgram(program);
+
+    let mut success = gl::FALSE as GLint;
+    gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+    if success != gl::TRUE as GLint {
+        let mut len = 0;
+        gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
+        let mut infoLog = vec![0u8; len.max(1) as usize];
+        let mut written = 0;
+        gl::GetProgramInfoLog(
+            program,
+            infoLog.len() as GLsizei,
+            &mut written,
+            infoLog.as_mut_ptr() as *mut GLchar,
+        );
+        infoLog.truncate(written as usize);
+        gl::DeleteProgram(program);
+        return Err(ShaderError::Link {
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+            log: parse_info_log(infoLog),
+        });
+    }
+    Ok(program)
+}
 
-        shader
+/// Drivers are inconsistent about whether the reported length includes the
+/// terminating NUL, so cut at the first one if there is one.
+fn parse_info_log(mut info_log: Vec<u8>) -> String {
+    if let Some(first_null_byte) = info_log.iter().position(|x| *x == 0) {
+        info_log.truncate(first_null_byte);
     }
+    String::from_utf8_lossy(&info_log).into_owned()
 }
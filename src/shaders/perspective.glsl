@@ -0,0 +1,31 @@
+// Projection from camera space to clip space.
+//
+// By default this takes the whole transform as a matrix. Define
+// MANUAL_PERSPECTIVE to compute it from the frustum scale and the near and
+// far planes instead, as the first perspective chapter does.
+#ifdef MANUAL_PERSPECTIVE
+uniform float zNear;
+uniform float zFar;
+uniform float frustumScale;
+
+vec4 perspective(vec4 cameraPos)
+{
+	vec4 clipPos;
+
+	clipPos.xy = cameraPos.xy * frustumScale;
+
+	clipPos.z = cameraPos.z * (zNear + zFar) / (zNear - zFar);
+	clipPos.z += 2 * zNear * zFar / (zNear - zFar);
+
+	clipPos.w = -cameraPos.z;
+
+	return clipPos;
+}
+#else
+uniform mat4 perspectiveMatrix;
+
+vec4 perspective(vec4 cameraPos)
+{
+	return perspectiveMatrix * cameraPos;
+}
+#endif
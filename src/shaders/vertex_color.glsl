@@ -0,0 +1,6 @@
+// Per-vertex position and color, with the color handed on to the fragment
+// shader. Shared by every vertex shader that draws the colored meshes.
+layout(location = 0) in vec4 position;
+layout(location = 1) in vec4 color;
+
+smooth out vec4 theColor;
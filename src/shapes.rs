@@ -0,0 +1,547 @@
+//! Generated meshes: cube, spheres, cylinder, cone, plane and torus.
+//!
+//! Every shape fits the unit cube around the origin, like the gltut `Unit*`
+//! meshes, and its triangles are clockwise seen from outside, to be drawn
+//! with `gl::FrontFace(gl::CW)` and back faces culled like the chapters do.
+//! The parameters set how finely the curved surfaces are split up; vertices
+//! are repeated where the normal changes, at edges and seams.
+//!
+//! Colors are the normals mapped to [0, 1], which tells the sides apart
+//! without any lighting.
+//! [`Shape::into_mesh_data`] puts position at location 0, color at 1 and
+//! normal at 2, ready for [`Mesh::new`](crate::mesh::Mesh::new).
+extern crate gl;
+
+use crate::mesh::{AttributeArray, Command, Indices, MeshData};
+
+use std::f32::consts::PI;
+
+/// Vertices and triangles, before they become a [`MeshData`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Shape {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub colors: Vec<[f32; 4]>,
+    /// Three per triangle.
+    pub indices: Vec<u32>,
+}
+
+impl Shape {
+    /// One triangle list, with 16 bit indices if they fit.
+    pub fn into_mesh_data(self) -> MeshData {
+        let indices = if self.positions.len() > usize::from(u16::MAX) {
+            Indices::U32(self.indices)
+        } else {
+            Indices::U16(self.indices.into_iter().map(|index| index as u16).collect())
+        };
+        MeshData {
+            attributes: vec![
+                AttributeArray::new(0, 3, flatten(&self.positions)),
+                AttributeArray::new(1, 4, flatten(&self.colors)),
+                AttributeArray::new(2, 3, flatten(&self.normals)),
+            ],
+            commands: vec![Command::Elements {
+                primitive: gl::TRIANGLES,
+                indices,
+                restart: None,
+            }],
+            ..MeshData::default()
+        }
+    }
+
+    fn vertex(&mut self, position: [f32; 3], normal: [f32; 3]) -> u32 {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.colors.push([
+            normal[0] * 0.5 + 0.5,
+            normal[1] * 0.5 + 0.5,
+            normal[2] * 0.5 + 0.5,
+            1.0,
+        ]);
+        self.positions.len() as u32 - 1
+    }
+
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.indices.extend_from_slice(&[a, b, c]);
+    }
+
+    /// A `columns` by `rows` grid of quads over `surface(u, v)`, with u and
+    /// v going from 0 to 1. The surface faces the side `du × dv` points to.
+    /// Triangles that collapse to a point, like those at the poles of a
+    /// sphere, are left out.
+    fn grid<F>(&mut self, columns: u32, rows: u32, surface: F)
+    where
+        F: Fn(f32, f32) -> ([f32; 3], [f32; 3]),
+    {
+        let first = self.positions.len() as u32;
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let (position, normal) =
+                    surface(column as f32 / columns as f32, row as f32 / rows as f32);
+                self.vertex(position, normal);
+            }
+        }
+        let index = |column: u32, row: u32| first + row * (columns + 1) + column;
+        for row in 0..rows {
+            for column in 0..columns {
+                let corners = [
+                    index(column, row),
+                    index(column + 1, row),
+                    index(column + 1, row + 1),
+                    index(column, row + 1),
+                ];
+                for &[a, b, c] in &[
+                    [corners[0], corners[2], corners[1]],
+                    [corners[0], corners[3], corners[2]],
+                ] {
+                    if !self.degenerate(a, b, c) {
+                        self.triangle(a, b, c);
+                    }
+                }
+            }
+        }
+    }
+
+    fn degenerate(&self, a: u32, b: u32, c: u32) -> bool {
+        let [a, b, c] = [a, b, c].map(|index| self.positions[index as usize]);
+        let cross = cross(sub(b, a), sub(c, a));
+        dot(cross, cross) < 1e-12
+    }
+
+    /// A disk of diameter 1 and `segments` triangles at height `y`, facing
+    /// up if `up` and down otherwise.
+    fn disk(&mut self, segments: u32, y: f32, up: bool) {
+        let normal = [0.0, if up { 1.0 } else { -1.0 }, 0.0];
+        let center = self.vertex([0.0, y, 0.0], normal);
+        let first = center + 1;
+        for segment in 0..=segments {
+            let (x, z) = circle(segment, segments);
+            self.vertex([x * 0.5, y, z * 0.5], normal);
+        }
+        for segment in 0..segments {
+            let (this, next) = (first + segment, first + segment + 1);
+            if up {
+                self.triangle(center, next, this);
+            } else {
+                self.triangle(center, this, next);
+            }
+        }
+    }
+}
+
+/// Builds one shape at a fixed level of detail.
+pub type Generator = fn() -> Shape;
+
+/// The shapes `view --shape` shows, split finely enough to look round.
+pub const SHAPES: &[(&str, Generator)] = &[
+    ("cube", || cube(1)),
+    ("sphere", || uv_sphere(32, 16)),
+    ("icosphere", || icosphere(3)),
+    ("cylinder", || cylinder(32)),
+    ("cone", || cone(32)),
+    ("plane", || plane(1, 1)),
+    ("torus", || torus(48, 24)),
+];
+
+/// A cube from -0.5 to 0.5, each face split into `subdivisions` by
+/// `subdivisions` quads.
+pub fn cube(subdivisions: u32) -> Shape {
+    let subdivisions = subdivisions.max(1);
+    // Each face as its normal and two axes along it, with the first cross
+    // the second pointing out.
+    let faces: [[[f32; 3]; 3]; 6] = [
+        [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        [[-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+        [[0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]],
+        [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+        [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        [[0.0, 0.0, -1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]],
+    ];
+    let mut shape = Shape::default();
+    for &[normal, u_axis, v_axis] in &faces {
+        shape.grid(subdivisions, subdivisions, |u, v| {
+            let mut position = [0.0; 3];
+            for axis in 0..3 {
+                position[axis] =
+                    normal[axis] * 0.5 + u_axis[axis] * (u - 0.5) + v_axis[axis] * (v - 0.5);
+            }
+            (position, normal)
+        });
+    }
+    shape
+}
+
+/// A sphere of diameter 1 made of `segments` slices around the y axis and
+/// `rings` bands from pole to pole.
+pub fn uv_sphere(segments: u32, rings: u32) -> Shape {
+    let (segments, rings) = (segments.max(3), rings.max(2));
+    let mut shape = Shape::default();
+    shape.grid(segments, rings, |u, v| {
+        let latitude = (v - 0.5) * PI;
+        let (x, z) = angle(u);
+        let normal = [latitude.cos() * x, latitude.sin(), latitude.cos() * z];
+        (scale(normal, 0.5), normal)
+    });
+    shape
+}
+
+/// A sphere of diameter 1 from an icosahedron whose triangles are split in
+/// four `subdivisions` times; the vertices are spread more evenly than on
+/// a [`uv_sphere`].
+pub fn icosphere(subdivisions: u32) -> Shape {
+    let t = (1.0 + 5f32.sqrt()) / 2.0;
+    let corners = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ];
+    // Clockwise seen from outside.
+    #[rustfmt::skip]
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 5, 11], [0, 1, 5], [0, 7, 1], [0, 10, 7], [0, 11, 10],
+        [1, 9, 5], [5, 4, 11], [11, 2, 10], [10, 6, 7], [7, 8, 1],
+        [3, 4, 9], [3, 2, 4], [3, 6, 2], [3, 8, 6], [3, 9, 8],
+        [4, 5, 9], [2, 11, 4], [6, 10, 2], [8, 7, 6], [9, 1, 8],
+    ];
+
+    let mut shape = Shape::default();
+    for &corner in &corners {
+        let normal = normalize(corner);
+        shape.vertex(scale(normal, 0.5), normal);
+    }
+    for _ in 0..subdivisions {
+        let mut middles = std::collections::HashMap::new();
+        let mut middle = |shape: &mut Shape, a: u32, b: u32| {
+            *middles.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let (pa, pb) = (shape.positions[a as usize], shape.positions[b as usize]);
+                let normal = normalize([pa[0] + pb[0], pa[1] + pb[1], pa[2] + pb[2]]);
+                shape.vertex(scale(normal, 0.5), normal)
+            })
+        };
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let ab = middle(&mut shape, a, b);
+                let bc = middle(&mut shape, b, c);
+                let ca = middle(&mut shape, c, a);
+                vec![[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+    for [a, b, c] in triangles {
+        shape.triangle(a, b, c);
+    }
+    shape
+}
+
+/// A cylinder of diameter 1 and height 1 along the y axis, with `segments`
+/// sides and closed at both ends.
+pub fn cylinder(segments: u32) -> Shape {
+    let segments = segments.max(3);
+    let mut shape = Shape::default();
+    shape.grid(segments, 1, |u, v| {
+        let (x, z) = angle(u);
+        ([x * 0.5, v - 0.5, z * 0.5], [x, 0.0, z])
+    });
+    shape.disk(segments, 0.5, true);
+    shape.disk(segments, -0.5, false);
+    shape
+}
+
+/// A cone of diameter 1 and height 1 along the y axis, point up, with
+/// `segments` sides and a closed base.
+pub fn cone(segments: u32) -> Shape {
+    let segments = segments.max(3);
+    let mut shape = Shape::default();
+    // The side leans in by as much as the radius over the height.
+    let side = |x: f32, z: f32| normalize([x, 0.5, z]);
+    let first = shape.positions.len() as u32;
+    for segment in 0..=segments {
+        let (x, z) = circle(segment, segments);
+        shape.vertex([x * 0.5, -0.5, z * 0.5], side(x, z));
+    }
+    // One tip per side, so each gets the normal of the middle of its side.
+    for segment in 0..segments {
+        let (x, z) = angle((segment as f32 + 0.5) / segments as f32);
+        let tip = shape.vertex([0.0, 0.5, 0.0], side(x, z));
+        shape.triangle(tip, first + segment + 1, first + segment);
+    }
+    shape.disk(segments, -0.5, false);
+    shape
+}
+
+/// A square from -0.5 to 0.5 in the xz plane, facing up, split into
+/// `columns` by `rows` quads.
+pub fn plane(columns: u32, rows: u32) -> Shape {
+    let mut shape = Shape::default();
+    shape.grid(columns.max(1), rows.max(1), |u, v| {
+        ([u - 0.5, 0.0, 0.5 - v], [0.0, 1.0, 0.0])
+    });
+    shape
+}
+
+/// A ring around the y axis, 1 across and 0.3 thick: a tube of `sides`
+/// sides bent around in `segments` steps.
+pub fn torus(segments: u32, sides: u32) -> Shape {
+    let (segments, sides) = (segments.max(3), sides.max(3));
+    let (major, minor) = (0.35, 0.15);
+    let mut shape = Shape::default();
+    shape.grid(segments, sides, |u, v| {
+        let (x, z) = angle(u);
+        let (cos, sin) = ((v * 2.0 * PI).cos(), (v * 2.0 * PI).sin());
+        let normal = [x * cos, sin, z * cos];
+        (
+            [
+                x * major + normal[0] * minor,
+                normal[1] * minor,
+                z * major + normal[2] * minor,
+            ],
+            normal,
+        )
+    });
+    shape
+}
+
+/// The point a fraction `turn` of the way around the unit circle in the xz
+/// plane, counter-clockwise seen from above. Whole turns land exactly on
+/// the start, so seams line up.
+fn angle(turn: f32) -> (f32, f32) {
+    if turn >= 1.0 {
+        return (1.0, 0.0);
+    }
+    let angle = turn * 2.0 * PI;
+    (angle.cos(), -angle.sin())
+}
+
+fn circle(step: u32, steps: u32) -> (f32, f32) {
+    angle(step as f32 / steps as f32)
+}
+
+fn flatten<const N: usize>(values: &[[f32; N]]) -> Vec<f32> {
+    values.iter().flatten().copied().collect()
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn scale(a: [f32; 3], factor: f32) -> [f32; 3] {
+    [a[0] * factor, a[1] * factor, a[2] * factor]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    scale(a, 1.0 / dot(a, a).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Vertices, triangles and valid indices.
+    fn counts(shape: &Shape) -> (usize, usize) {
+        assert_eq!(shape.normals.len(), shape.positions.len());
+        assert_eq!(shape.colors.len(), shape.positions.len());
+        assert_eq!(shape.indices.len() % 3, 0);
+        assert!(shape
+            .indices
+            .iter()
+            .all(|&index| (index as usize) < shape.positions.len()));
+        (shape.positions.len(), shape.indices.len() / 3)
+    }
+
+    fn triangles(shape: &Shape) -> impl Iterator<Item = [usize; 3]> + '_ {
+        shape
+            .indices
+            .chunks(3)
+            .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+    }
+
+    /// Every triangle is clockwise seen from the side its normals point
+    /// to, and every normal is a unit vector.
+    fn assert_clockwise(shape: &Shape) {
+        for normal in &shape.normals {
+            assert!((dot(*normal, *normal) - 1.0).abs() < 1e-4, "{:?}", normal);
+        }
+        for [a, b, c] in triangles(shape) {
+            let p = &shape.positions;
+            let n = &shape.normals;
+            let facing = cross(sub(p[b], p[a]), sub(p[c], p[a]));
+            let outside = [
+                n[a][0] + n[b][0] + n[c][0],
+                n[a][1] + n[b][1] + n[c][1],
+                n[a][2] + n[b][2] + n[c][2],
+            ];
+            assert!(
+                dot(facing, outside) < 0.0,
+                "triangle {:?} is counter-clockwise",
+                [p[a], p[b], p[c]]
+            );
+        }
+    }
+
+    /// Directed edges between welded positions, and how often each is used.
+    fn edges(shape: &Shape) -> HashMap<([i32; 3], [i32; 3]), u32> {
+        let weld = |index: usize| shape.positions[index].map(|x| (x * 1e4).round() as i32);
+        let mut edges = HashMap::new();
+        for [a, b, c] in triangles(shape) {
+            let [a, b, c] = [weld(a), weld(b), weld(c)];
+            assert!(a != b && b != c && c != a, "degenerate triangle");
+            for edge in [(a, b), (b, c), (c, a)] {
+                *edges.entry(edge).or_insert(0) += 1;
+            }
+        }
+        edges
+    }
+
+    /// Every edge has exactly one triangle on each side, going the other
+    /// way along it, so there are no holes and the winding is consistent.
+    fn assert_closed(shape: &Shape) {
+        let edges = edges(shape);
+        for (&(a, b), &count) in &edges {
+            assert_eq!(count, 1, "edge {:?} {:?} is used {} times", a, b, count);
+            assert_eq!(edges.get(&(b, a)), Some(&1), "edge {:?} {:?} is open", a, b);
+        }
+    }
+
+    /// Every position is within the unit cube around the origin.
+    fn assert_unit(shape: &Shape) {
+        for position in &shape.positions {
+            assert!(
+                position.iter().all(|x| x.abs() <= 0.5 + 1e-6),
+                "{:?}",
+                position
+            );
+        }
+    }
+
+    fn check(shape: &Shape) {
+        assert_clockwise(shape);
+        assert_closed(shape);
+        assert_unit(shape);
+    }
+
+    #[test]
+    fn cube() {
+        assert_eq!(counts(&super::cube(1)), (24, 12));
+        for n in 1..4 {
+            let shape = super::cube(n);
+            let n = n as usize;
+            assert_eq!(counts(&shape), (6 * (n + 1) * (n + 1), 12 * n * n));
+            check(&shape);
+        }
+    }
+
+    #[test]
+    fn uv_sphere() {
+        for &(segments, rings) in &[(3, 2), (8, 4), (32, 16)] {
+            let shape = super::uv_sphere(segments, rings);
+            let (s, r) = (segments as usize, rings as usize);
+            // The bands at the poles are triangles rather than quads.
+            assert_eq!(counts(&shape), ((s + 1) * (r + 1), 2 * s * (r - 1)));
+            check(&shape);
+        }
+    }
+
+    #[test]
+    fn icosphere() {
+        for n in 0..4 {
+            let shape = super::icosphere(n);
+            let faces = 20 * 4usize.pow(n);
+            assert_eq!(counts(&shape), (faces / 2 + 2, faces));
+            check(&shape);
+            for position in &shape.positions {
+                assert!((dot(*position, *position).sqrt() - 0.5).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn cylinder() {
+        for segments in [3, 8, 40] {
+            let shape = super::cylinder(segments);
+            let s = segments as usize;
+            assert_eq!(counts(&shape), (4 * s + 6, 4 * s));
+            check(&shape);
+        }
+    }
+
+    #[test]
+    fn cone() {
+        for segments in [3, 8, 40] {
+            let shape = super::cone(segments);
+            let s = segments as usize;
+            assert_eq!(counts(&shape), (3 * s + 3, 2 * s));
+            check(&shape);
+        }
+    }
+
+    #[test]
+    fn torus() {
+        for &(segments, sides) in &[(3, 3), (24, 12)] {
+            let shape = super::torus(segments, sides);
+            let (s, t) = (segments as usize, sides as usize);
+            assert_eq!(counts(&shape), ((s + 1) * (t + 1), 2 * s * t));
+            check(&shape);
+        }
+    }
+
+    #[test]
+    fn plane() {
+        let shape = super::plane(4, 3);
+        assert_eq!(counts(&shape), (5 * 4, 2 * 4 * 3));
+        assert_clockwise(&shape);
+        assert_unit(&shape);
+        assert!(shape.normals.iter().all(|&n| n == [0.0, 1.0, 0.0]));
+        // Open only around the border, where each edge has a single side.
+        let edges = edges(&shape);
+        let border = edges
+            .keys()
+            .filter(|&&(a, b)| !edges.contains_key(&(b, a)))
+            .count();
+        assert_eq!(border, 2 * (4 + 3));
+    }
+
+    #[test]
+    fn mesh_data() {
+        let data = super::cube(2).into_mesh_data();
+        assert_eq!(data.validate(), Ok(54));
+        assert_eq!(data.attributes[1].values.len(), 54 * 4);
+        match &data.commands[..] {
+            [Command::Elements {
+                indices: Indices::U16(indices),
+                ..
+            }] => assert_eq!(indices.len(), 6 * 8 * 3),
+            other => panic!("unexpected commands {:?}", other),
+        }
+        let data = super::uv_sphere(300, 300).into_mesh_data();
+        assert!(matches!(
+            data.commands[0],
+            Command::Elements {
+                indices: Indices::U32(_),
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,156 @@
+//! Values that can be passed to [`Shader::set_uniform`].
+//!
+//! [`Shader::set_uniform`]: crate::shader::Shader::set_uniform
+extern crate gl;
+use self::gl::types::*;
+
+use cgmath::{Matrix3, Matrix4, Vector2, Vector3, Vector4};
+
+/// A uniform value in the shape the `glUniform*v` calls want it: a flat
+/// array of one or more elements. The shader keeps the last one set for
+/// every name so it can restore them after a reload.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Uniform {
+    /// `int`, `ivec2`..`ivec4` and `bool`, or arrays of them.
+    Int { components: usize, values: Vec<i32> },
+    /// `float`, `vec2`..`vec4`, or arrays of them.
+    Float { components: usize, values: Vec<f32> },
+    /// Column major, 9 floats per matrix.
+    Mat3(Vec<f32>),
+    /// Column major, 16 floats per matrix.
+    Mat4(Vec<f32>),
+}
+
+impl Uniform {
+    /// Upload to `location` of the program currently in use.
+    pub unsafe fn apply(&self, location: GLint) {
+        match self {
+            Uniform::Int { components, values } => {
+                let count = (values.len() / components) as GLsizei;
+                let values = values.as_ptr();
+                match components {
+                    1 => gl::Uniform1iv(location, count, values),
+                    2 => gl::Uniform2iv(location, count, values),
+                    3 => gl::Uniform3iv(location, count, values),
+                    _ => gl::Uniform4iv(location, count, values),
+                }
+            }
+            Uniform::Float { components, values } => {
+                let count = (values.len() / components) as GLsizei;
+                let values = values.as_ptr();
+                match components {
+                    1 => gl::Uniform1fv(location, count, values),
+                    2 => gl::Uniform2fv(location, count, values),
+                    3 => gl::Uniform3fv(location, count, values),
+                    _ => gl::Uniform4fv(location, count, values),
+                }
+            }
+            Uniform::Mat3(values) => gl::UniformMatrix3fv(
+                location,
+                (values.len() / 9) as GLsizei,
+                gl::FALSE,
+                values.as_ptr(),
+            ),
+            Uniform::Mat4(values) => gl::UniformMatrix4fv(
+                location,
+                (values.len() / 16) as GLsizei,
+                gl::FALSE,
+                values.as_ptr(),
+            ),
+        }
+    }
+}
+
+/// Anything that can be set as a uniform.
+pub trait UniformValue {
+    fn to_uniform(&self) -> Uniform;
+}
+
+/// A single element of a uniform, which also makes slices and arrays of it
+/// uniform values.
+pub trait UniformElement: Copy {
+    fn to_uniforms(values: &[Self]) -> Uniform;
+}
+
+impl<T: UniformElement> UniformValue for [T] {
+    fn to_uniform(&self) -> Uniform {
+        T::to_uniforms(self)
+    }
+}
+
+impl<T: UniformElement, const N: usize> UniformValue for [T; N] {
+    fn to_uniform(&self) -> Uniform {
+        T::to_uniforms(self)
+    }
+}
+
+impl<T: UniformValue + ?Sized> UniformValue for &T {
+    fn to_uniform(&self) -> Uniform {
+        (**self).to_uniform()
+    }
+}
+
+// A blanket impl over `UniformElement` would overlap with the one for
+// references, so every element type gets its own.
+macro_rules! uniform_value {
+    ($($ty:ty),*) => {
+        $(
+            impl UniformValue for $ty {
+                fn to_uniform(&self) -> Uniform {
+                    UniformElement::to_uniforms(std::slice::from_ref(self))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! uniform_element {
+    ($ty:ty, $variant:ident, $components:expr, |$v:ident| $flatten:expr) => {
+        impl UniformElement for $ty {
+            fn to_uniforms(values: &[Self]) -> Uniform {
+                Uniform::$variant {
+                    components: $components,
+                    values: values.iter().flat_map(|$v| $flatten).collect(),
+                }
+            }
+        }
+        uniform_value!($ty);
+    };
+}
+
+uniform_element!(bool, Int, 1, |v| [*v as i32]);
+uniform_element!(i32, Int, 1, |v| [*v]);
+uniform_element!((i32, i32), Int, 2, |v| [v.0, v.1]);
+uniform_element!((i32, i32, i32), Int, 3, |v| [v.0, v.1, v.2]);
+uniform_element!((i32, i32, i32, i32), Int, 4, |v| [v.0, v.1, v.2, v.3]);
+uniform_element!(f32, Float, 1, |v| [*v]);
+uniform_element!((f32, f32), Float, 2, |v| [v.0, v.1]);
+uniform_element!((f32, f32, f32), Float, 3, |v| [v.0, v.1, v.2]);
+uniform_element!((f32, f32, f32, f32), Float, 4, |v| [v.0, v.1, v.2, v.3]);
+uniform_element!(Vector2<f32>, Float, 2, |v| *AsRef::<[f32; 2]>::as_ref(v));
+uniform_element!(Vector3<f32>, Float, 3, |v| *AsRef::<[f32; 3]>::as_ref(v));
+uniform_element!(Vector4<f32>, Float, 4, |v| *AsRef::<[f32; 4]>::as_ref(v));
+
+impl UniformElement for Matrix3<f32> {
+    fn to_uniforms(values: &[Self]) -> Uniform {
+        Uniform::Mat3(
+            values
+                .iter()
+                .flat_map(|m| *AsRef::<[f32; 9]>::as_ref(m))
+                .collect(),
+        )
+    }
+}
+
+impl UniformElement for Matrix4<f32> {
+    fn to_uniforms(values: &[Self]) -> Uniform {
+        Uniform::Mat4(
+            values
+                .iter()
+                .flat_map(|m| *AsRef::<[f32; 16]>::as_ref(m))
+                .collect(),
+        )
+    }
+}
+
+uniform_value!(Matrix3<f32>, Matrix4<f32>);
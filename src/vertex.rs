@@ -0,0 +1,480 @@
+//! Describing what's in a vertex buffer.
+//!
+//! A [`VertexLayout`] lists the attributes of a vertex and how the buffer
+//! packs them, and turns that into the `glVertexAttribPointer` calls, so the
+//! byte offsets don't have to be worked out by hand. Buffers are either
+//! interleaved, one whole vertex after the other, or planar, all positions
+//! first and then all colors, the way the tutorials lay out their arrays.
+//!
+//! Vertex structs get their layout from the [`Vertex`] trait, usually
+//! implemented with [`vertex!`](crate::vertex!).
+extern crate gl;
+use self::gl::types::*;
+
+use crate::objects::{ArrayBuffer, VertexArrayBinding};
+
+/// The type of one component of an attribute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ComponentType {
+    F32,
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+}
+
+impl ComponentType {
+    pub fn gl_enum(self) -> GLenum {
+        match self {
+            ComponentType::F32 => gl::FLOAT,
+            ComponentType::I8 => gl::BYTE,
+            ComponentType::U8 => gl::UNSIGNED_BYTE,
+            ComponentType::I16 => gl::SHORT,
+            ComponentType::U16 => gl::UNSIGNED_SHORT,
+            ComponentType::I32 => gl::INT,
+            ComponentType::U32 => gl::UNSIGNED_INT,
+        }
+    }
+
+    /// Size in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            ComponentType::I8 | ComponentType::U8 => 1,
+            ComponentType::I16 | ComponentType::U16 => 2,
+            ComponentType::F32 | ComponentType::I32 | ComponentType::U32 => 4,
+        }
+    }
+}
+
+/// One input of the vertex shader.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attribute {
+    pub location: u32,
+    /// 1 to 4.
+    pub components: usize,
+    pub kind: ComponentType,
+    /// Scale integers to [0, 1] or [-1, 1] instead of converting them as is.
+    pub normalized: bool,
+    /// Where the attribute starts within a vertex. Only used by interleaved
+    /// layouts; `None` packs it right after the previous attribute.
+    pub offset: Option<usize>,
+}
+
+impl Attribute {
+    /// Size of one value in bytes.
+    pub fn size(&self) -> usize {
+        self.components * self.kind.size()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Packing {
+    /// Whole vertices one after the other.
+    Interleaved,
+    /// One block per attribute, in the order they were added. Each block
+    /// starts on a 4 byte boundary.
+    Planar,
+}
+
+/// Where the values of one attribute are in a buffer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pointer {
+    pub attribute: Attribute,
+    /// Bytes from the start of the buffer to the first value.
+    pub offset: usize,
+    /// Bytes between two values.
+    pub stride: usize,
+}
+
+impl Pointer {
+    /// Feed the attribute of the bound vertex array from `buffer`.
+    pub fn apply(&self, vao: &VertexArrayBinding, buffer: &ArrayBuffer) {
+        let attribute = self.attribute;
+        vao.attribute_pointer(
+            attribute.location,
+            buffer,
+            attribute.components as i32,
+            attribute.kind.gl_enum(),
+            attribute.normalized,
+            self.stride,
+            self.offset,
+        );
+    }
+}
+
+/// The attributes of a vertex, and how a buffer packs them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VertexLayout {
+    packing: Packing,
+    attributes: Vec<Attribute>,
+    /// Bytes per vertex for interleaved layouts, if not just the sum of
+    /// the attributes, e.g. because a struct has padding.
+    stride: Option<usize>,
+}
+
+impl VertexLayout {
+    pub fn interleaved() -> VertexLayout {
+        VertexLayout {
+            packing: Packing::Interleaved,
+            attributes: Vec::new(),
+            stride: None,
+        }
+    }
+
+    pub fn planar() -> VertexLayout {
+        VertexLayout {
+            packing: Packing::Planar,
+            attributes: Vec::new(),
+            stride: None,
+        }
+    }
+
+    /// Add an attribute of `components` values of type `kind`.
+    pub fn attribute(self, location: u32, components: usize, kind: ComponentType) -> VertexLayout {
+        self.push(Attribute {
+            location,
+            components,
+            kind,
+            normalized: false,
+            offset: None,
+        })
+    }
+
+    /// Add an attribute with everything spelled out.
+    ///
+    /// # Panics
+    ///
+    /// If `location` is already taken, or `components` isn't 1 to 4.
+    pub fn push(mut self, attribute: Attribute) -> VertexLayout {
+        assert!(
+            (1..=4).contains(&attribute.components),
+            "Attribute {} has {} components",
+            attribute.location,
+            attribute.components
+        );
+        assert!(
+            self.attributes
+                .iter()
+                .all(|a| a.location != attribute.location),
+            "Attribute location {} is used twice",
+            attribute.location
+        );
+        self.attributes.push(attribute);
+        self
+    }
+
+    /// Bytes from one interleaved vertex to the next, e.g.
+    /// `mem::size_of::<V>()`.
+    pub fn with_stride(mut self, stride: usize) -> VertexLayout {
+        self.stride = Some(stride);
+        self
+    }
+
+    /// Bytes taken up by one vertex.
+    pub fn vertex_size(&self) -> usize {
+        match (self.packing, self.stride) {
+            (Packing::Interleaved, Some(stride)) => stride,
+            _ => {
+                let mut end = 0;
+                for (attribute, offset) in self.attributes.iter().zip(self.offsets()) {
+                    end = end.max(offset + attribute.size());
+                }
+                end
+            }
+        }
+    }
+
+    /// Offsets of the attributes within an interleaved vertex.
+    fn offsets(&self) -> Vec<usize> {
+        let mut next = 0;
+        self.attributes
+            .iter()
+            .map(|attribute| {
+                let offset = attribute.offset.unwrap_or(next);
+                next = offset + attribute.size();
+                offset
+            })
+            .collect()
+    }
+
+    /// Bytes taken up by `count` vertices.
+    pub fn buffer_size(&self, count: usize) -> usize {
+        match self.packing {
+            Packing::Interleaved => count * self.vertex_size(),
+            Packing::Planar => self
+                .attributes
+                .iter()
+                .map(|attribute| align(count * attribute.size()))
+                .sum(),
+        }
+    }
+
+    /// Where each attribute of vertex `first` is, in a buffer holding
+    /// `count` vertices. Planar layouts need `count` to find the blocks;
+    /// `first` lets a draw start partway through them, the way the objects
+    /// of a scene can share one buffer.
+    pub fn pointers(&self, count: usize, first: usize) -> Vec<Pointer> {
+        match self.packing {
+            Packing::Interleaved => {
+                let stride = self.vertex_size();
+                self.attributes
+                    .iter()
+                    .zip(self.offsets())
+                    .map(|(&attribute, offset)| Pointer {
+                        attribute,
+                        offset: first * stride + offset,
+                        stride,
+                    })
+                    .collect()
+            }
+            Packing::Planar => {
+                let mut block = 0;
+                self.attributes
+                    .iter()
+                    .map(|&attribute| {
+                        let size = attribute.size();
+                        let pointer = Pointer {
+                            attribute,
+                            offset: block + first * size,
+                            stride: size,
+                        };
+                        block += align(count * size);
+                        pointer
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Feed the attributes of the bound vertex array from `buffer`, which
+    /// holds `count` vertices, starting at vertex `first`.
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is too small for `count` vertices.
+    pub fn apply(
+        &self,
+        vao: &VertexArrayBinding,
+        buffer: &ArrayBuffer,
+        count: usize,
+        first: usize,
+    ) {
+        let size = self.buffer_size(count);
+        assert!(
+            size <= buffer.size(),
+            "{} vertices take {} bytes, but the buffer only has {}",
+            count,
+            size,
+            buffer.size()
+        );
+        for pointer in self.pointers(count, first) {
+            pointer.apply(vao, buffer);
+        }
+    }
+}
+
+/// Round `size` up to a multiple of 4 bytes, so that whatever follows is
+/// aligned for any component type.
+pub fn align(size: usize) -> usize {
+    (size + 3) & !3
+}
+
+/// A `#[repr(C)]` vertex struct, to be uploaded as an interleaved buffer.
+///
+/// # Safety
+///
+/// `layout` has to describe the fields as they are in memory.
+pub unsafe trait Vertex: Copy {
+    fn layout() -> VertexLayout;
+}
+
+/// A field type that can be a vertex attribute: a number or an array of up
+/// to 4 of them.
+pub trait AttributeType {
+    const COMPONENTS: usize;
+    const KIND: ComponentType;
+}
+
+macro_rules! attribute_types {
+    ($($ty:ty => $kind:ident),*) => {
+        $(
+            impl AttributeType for $ty {
+                const COMPONENTS: usize = 1;
+                const KIND: ComponentType = ComponentType::$kind;
+            }
+
+            impl<const N: usize> AttributeType for [$ty; N] {
+                const COMPONENTS: usize = N;
+                const KIND: ComponentType = ComponentType::$kind;
+            }
+        )*
+    };
+}
+
+attribute_types!(f32 => F32, i8 => I8, u8 => U8, i16 => I16, u16 => U16, i32 => I32, u32 => U32);
+
+/// The attribute for a field `T` at `offset` bytes into the struct; used by
+/// [`vertex!`](crate::vertex!), which can name a field but not its type.
+pub fn field<V, T: AttributeType>(
+    _field: fn(&V) -> &T,
+    location: u32,
+    offset: usize,
+    normalized: bool,
+) -> Attribute {
+    Attribute {
+        location,
+        components: T::COMPONENTS,
+        kind: T::KIND,
+        normalized,
+        offset: Some(offset),
+    }
+}
+
+/// Implement [`Vertex`] for a `#[repr(C)]` struct, giving each field its
+/// attribute location:
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(Clone, Copy)]
+/// struct ColorVertex {
+///     position: [f32; 3],
+///     color: [u8; 4],
+/// }
+///
+/// vertex!(ColorVertex { position: 0, color: 1 normalized });
+/// ```
+#[macro_export]
+macro_rules! vertex {
+    ($ty:ident { $($field:ident: $location:literal $($normalized:ident)?),* $(,)? }) => {
+        unsafe impl $crate::vertex::Vertex for $ty {
+            fn layout() -> $crate::vertex::VertexLayout {
+                $crate::vertex::VertexLayout::interleaved()
+                    $(.push($crate::vertex::field(
+                        |vertex: &$ty| &vertex.$field,
+                        $location,
+                        std::mem::offset_of!($ty, $field),
+                        $crate::vertex!(@normalized $($normalized)?),
+                    )))*
+                    .with_stride(std::mem::size_of::<$ty>())
+            }
+        }
+    };
+    (@normalized normalized) => { true };
+    (@normalized) => { false };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Padded {
+        color: [u8; 3],
+        position: [f32; 3],
+        id: u16,
+    }
+
+    crate::vertex!(Padded { color: 1 normalized, position: 0, id: 2 });
+
+    fn offsets_and_strides(pointers: &[Pointer]) -> Vec<(u32, usize, usize)> {
+        pointers
+            .iter()
+            .map(|pointer| (pointer.attribute.location, pointer.offset, pointer.stride))
+            .collect()
+    }
+
+    #[test]
+    fn vertex_struct_with_padding() {
+        let layout = Padded::layout();
+        assert_eq!(std::mem::size_of::<Padded>(), 20);
+        assert_eq!(layout.vertex_size(), 20);
+        assert_eq!(
+            offsets_and_strides(&layout.pointers(1, 0)),
+            [(1, 0, 20), (0, 4, 20), (2, 16, 20)]
+        );
+
+        let pointers = layout.pointers(1, 0);
+        assert_eq!(
+            pointers[0].attribute,
+            Attribute {
+                location: 1,
+                components: 3,
+                kind: ComponentType::U8,
+                normalized: true,
+                offset: Some(0),
+            }
+        );
+        assert_eq!(pointers[1].attribute.kind, ComponentType::F32);
+        assert!(!pointers[1].attribute.normalized);
+        assert_eq!(pointers[2].attribute.components, 1);
+        assert_eq!(pointers[2].attribute.kind, ComponentType::U16);
+    }
+
+    #[test]
+    fn interleaved() {
+        let layout = VertexLayout::interleaved()
+            .attribute(0, 3, ComponentType::F32)
+            .attribute(1, 4, ComponentType::U8);
+        assert_eq!(layout.vertex_size(), 16);
+        assert_eq!(layout.buffer_size(10), 160);
+        assert_eq!(
+            offsets_and_strides(&layout.pointers(10, 0)),
+            [(0, 0, 16), (1, 12, 16)]
+        );
+        // The count doesn't matter, only where the first vertex is.
+        assert_eq!(
+            offsets_and_strides(&layout.pointers(3, 2)),
+            [(0, 32, 16), (1, 44, 16)]
+        );
+
+        let padded = layout.with_stride(20);
+        assert_eq!(padded.buffer_size(10), 200);
+        assert_eq!(
+            offsets_and_strides(&padded.pointers(10, 1)),
+            [(0, 20, 20), (1, 32, 20)]
+        );
+    }
+
+    #[test]
+    fn planar() {
+        // 3 vertices: 36 bytes of positions, then 9 of colors padded to 12.
+        let layout = VertexLayout::planar()
+            .attribute(0, 3, ComponentType::F32)
+            .attribute(1, 3, ComponentType::U8);
+        assert_eq!(layout.vertex_size(), 15);
+        assert_eq!(layout.buffer_size(3), 48);
+        assert_eq!(
+            offsets_and_strides(&layout.pointers(3, 0)),
+            [(0, 0, 12), (1, 36, 3)]
+        );
+        assert_eq!(
+            offsets_and_strides(&layout.pointers(3, 1)),
+            [(0, 12, 12), (1, 39, 3)]
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        assert_eq!(align(0), 0);
+        assert_eq!(align(1), 4);
+        assert_eq!(align(4), 4);
+        assert_eq!(align(9), 12);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attribute location 0 is used twice")]
+    fn location_used_twice() {
+        VertexLayout::planar()
+            .attribute(0, 3, ComponentType::F32)
+            .attribute(0, 4, ComponentType::F32);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attribute 0 has 5 components")]
+    fn too_many_components() {
+        VertexLayout::planar().attribute(0, 5, ComponentType::F32);
+    }
+}
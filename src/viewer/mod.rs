@@ -0,0 +1,181 @@
+//! `gltut view <file>`: a mesh from a file, turning slowly in front of the
+//! camera.
+//!
+//! The projection is the one of ch_5_1. The model is scaled and moved so its
+//! bounding sphere sits in the middle of that frustum, and lit from a fixed
+//! direction if the file has normals. G steps through the groups of the
+//! model one at a time, then back to all of them.
+use crate::framework::{boxed, Chapter, InitError, Tutorial};
+use crate::mesh::{Mesh, MeshData, Values};
+use crate::section_ii::ch_5_1::{perspective_matrix, reshape};
+use crate::shader::Shader;
+
+extern crate gl;
+
+use cgmath::Vector3;
+use glfw::{Action, Key};
+
+use std::sync::OnceLock;
+
+/// `Chapter::init` can't take arguments, so the model waits here.
+static MODEL: OnceLock<MeshData> = OnceLock::new();
+
+/// Radius the model is scaled to, in camera space.
+const RADIUS: f32 = 0.5;
+/// Camera space depth of the model's center, halfway between the planes.
+const DEPTH: f32 = -1.75;
+
+/// The viewer as a chapter showing `data`. Can only be called once.
+pub fn chapter(data: MeshData) -> Chapter {
+    if MODEL.set(data).is_err() {
+        panic!("The viewer can only show one model");
+    }
+    Chapter {
+        key: "view",
+        description: "A mesh loaded from a file",
+        init: boxed::<Viewer>,
+    }
+}
+
+pub struct Viewer {
+    shader: Shader,
+    mesh: Mesh,
+    /// The sub-mesh G picked, or `None` to draw all of them.
+    group: Option<usize>,
+    frustum_scale: f32,
+    perspective_matrix: cgmath::Matrix4<f32>,
+}
+
+impl Tutorial for Viewer {
+    fn init() -> Result<Self, InitError> {
+        let data = MODEL.get().expect("viewer::chapter sets the model");
+        let shader = Shader::new(
+            "./src/viewer/shaders/view.vs",
+            "./src/section_ii/shaders/standard.fs",
+        )?;
+
+        let frustum_scale = 1.0f32;
+        let z_near = 0.5f32;
+        let z_far = 3.0f32;
+
+        let matrix = perspective_matrix(frustum_scale, z_near, z_far);
+
+        let mesh = Mesh::new(data)?;
+        let (center, radius) = bounds(data);
+        let scale = RADIUS / radius;
+        let lit = data
+            .attributes
+            .iter()
+            .any(|attribute| attribute.location == 2);
+
+        unsafe {
+            let _program = shader.bind();
+            shader.set_uniform("perspectiveMatrix", matrix);
+            shader.set_uniform("scale", scale);
+            shader.set_uniform("offset", Vector3::new(0.0, 0.0, DEPTH) - center * scale);
+            shader.set_uniform("lit", lit);
+
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        Ok(Viewer {
+            shader,
+            mesh,
+            group: None,
+            frustum_scale,
+            perspective_matrix: matrix,
+        })
+    }
+
+    fn display(&mut self, time: f64) {
+        unsafe {
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            let _program = self.shader.bind();
+            self.shader.set_uniform("angle", (time * 0.5) as f32);
+            match self.group {
+                Some(group) => self
+                    .mesh
+                    .render_sub_mesh(&self.mesh.sub_meshes()[group].name),
+                None => self.mesh.render(),
+            }
+        }
+    }
+
+    fn keyboard(&mut self, key: Key, action: Action, _modifiers: glfw::Modifiers) {
+        let groups = self.mesh.sub_meshes();
+        if key == Key::G && action == Action::Press && !groups.is_empty() {
+            self.group = match self.group {
+                None => Some(0),
+                Some(group) if group + 1 < groups.len() => Some(group + 1),
+                Some(_) => None,
+            };
+            match self.group {
+                Some(group) => println!("Showing group {}", groups[group].name),
+                None => println!("Showing every group"),
+            }
+        }
+    }
+
+    fn reshape(&mut self, width: i32, height: i32) {
+        unsafe {
+            reshape(
+                width,
+                height,
+                self.frustum_scale,
+                &mut self.perspective_matrix,
+                &self.shader,
+            )
+        }
+    }
+
+    fn shaders(&mut self) -> Vec<&mut Shader> {
+        vec![&mut self.shader]
+    }
+
+    fn teardown(&mut self) {
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+/// Center and radius of a sphere around the positions, the middle of their
+/// bounding box and its half diagonal. Positions that aren't floats are
+/// shown as they are.
+fn bounds(data: &MeshData) -> (Vector3<f32>, f32) {
+    let positions = data
+        .attributes
+        .iter()
+        .find(|attribute| attribute.location == 0);
+    let (components, values) = match positions {
+        Some(attribute) => match &attribute.values {
+            Values::F32(values) => (attribute.components, values),
+            _ => return (Vector3::new(0.0, 0.0, 0.0), RADIUS),
+        },
+        None => return (Vector3::new(0.0, 0.0, 0.0), RADIUS),
+    };
+
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for position in values.chunks(components) {
+        for axis in 0..components.min(3) {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+        }
+    }
+    for axis in components.min(3)..3 {
+        min[axis] = 0.0;
+        max[axis] = 0.0;
+    }
+
+    let center = Vector3::new(
+        (min[0] + max[0]) / 2.0,
+        (min[1] + max[1]) / 2.0,
+        (min[2] + max[2]) / 2.0,
+    );
+    let half = Vector3::new(max[0] - min[0], max[1] - min[1], max[2] - min[2]) / 2.0;
+    let radius = (half.x * half.x + half.y * half.y + half.z * half.z).sqrt();
+    (center, if radius > 0.0 { radius } else { RADIUS })
+}
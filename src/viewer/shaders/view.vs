@@ -0,0 +1,33 @@
+#version 330
+
+#include "../../shaders/vertex_color.glsl"
+#include "../../shaders/perspective.glsl"
+
+layout(location = 2) in vec3 normal;
+
+uniform vec3 offset;
+uniform float scale;
+uniform float angle;
+uniform bool lit;
+
+void main()
+{
+	mat3 rotation = mat3(
+		cos(angle), 0.0, -sin(angle),
+		0.0,        1.0, 0.0,
+		sin(angle), 0.0, cos(angle));
+
+	vec4 cameraPos = vec4(rotation * (position.xyz * scale) + offset, 1.0);
+
+	gl_Position = perspective(cameraPos);
+	if (lit)
+	{
+		vec3 toLight = normalize(vec3(0.5, 1.0, 1.0));
+		float diffuse = max(dot(normalize(rotation * normal), toLight), 0.0);
+		theColor = vec4(color.rgb * (0.25 + 0.75 * diffuse), color.a);
+	}
+	else
+	{
+		theColor = color;
+	}
+}
@@ -0,0 +1,178 @@
+//! Golden-image regression tests.
+//!
+//! Every chapter registered in `main.rs` is rendered offscreen with
+//! `--headless` at a fixed time and compared against the reference image in
+//! `tests/golden/`, named after the test. A few chapters are rendered again
+//! at other sizes, so a chapter that isn't told its aspect ratio shows up. On a mismatch the rendered frame and a diff image (every
+//! pixel outside the tolerance painted red) are left next to the test output
+//! so they can be inspected.
+//!
+//! After an intentional change to what a chapter draws, regenerate the
+//! references with `UPDATE_GOLDEN=1 cargo test --test golden`.
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Tutorial time every chapter is rendered at. Far enough in that the
+/// animated chapters have visibly moved away from their starting position.
+const TIME: &str = "1.25";
+
+/// Largest difference allowed in any channel of a single pixel, to absorb
+/// rounding differences between GL implementations.
+const TOLERANCE: u8 = 3;
+
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+fn load_png(path: &Path) -> Image {
+    let file = File::open(path).unwrap_or_else(|err| panic!("Failed to open {:?}: {}", path, err));
+    let decoder = png::Decoder::new(file);
+    let (info, mut reader) = decoder
+        .read_info()
+        .unwrap_or_else(|err| panic!("Failed to decode {:?}: {}", path, err));
+    assert_eq!(
+        info.color_type,
+        png::ColorType::RGBA,
+        "{:?} is not an RGBA image",
+        path
+    );
+    let mut pixels = vec![0; info.buffer_size()];
+    reader
+        .next_frame(&mut pixels)
+        .unwrap_or_else(|err| panic!("Failed to decode {:?}: {}", path, err));
+    Image {
+        width: info.width,
+        height: info.height,
+        pixels,
+    }
+}
+
+fn save_png(path: &Path, image: &Image) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, image.width, image.height);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&image.pixels).unwrap();
+}
+
+fn render(chapter: &str, args: &[&str], out_dir: &Path) -> PathBuf {
+    let output = Command::new(env!("CARGO_BIN_EXE_gltut"))
+        // shader paths are relative to the crate root
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args([chapter, "--headless", "--frames", "1", "--time", TIME])
+        .args(args)
+        .arg("--out")
+        .arg(out_dir)
+        .output()
+        .expect("Failed to run gltut");
+    assert!(
+        output.status.success(),
+        "Rendering {} failed:\n{}",
+        chapter,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    out_dir.join("frame_0000.png")
+}
+
+/// Returns the number of pixels outside the tolerance along with an image
+/// that marks them.
+fn diff(actual: &Image, expected: &Image) -> (usize, Image) {
+    let mut mismatched = 0;
+    let mut pixels = Vec::with_capacity(actual.pixels.len());
+    for (a, e) in actual.pixels.chunks(4).zip(expected.pixels.chunks(4)) {
+        let within = a.iter().zip(e).all(|(a, e)| a.abs_diff(*e) <= TOLERANCE);
+        if within {
+            // keep the scene recognisable but faded
+            pixels.extend_from_slice(&[e[0] / 4, e[1] / 4, e[2] / 4, 255]);
+        } else {
+            mismatched += 1;
+            pixels.extend_from_slice(&[255, 0, 0, 255]);
+        }
+    }
+    (
+        mismatched,
+        Image {
+            width: actual.width,
+            height: actual.height,
+            pixels,
+        },
+    )
+}
+
+fn check(name: &str, chapter: &str, args: &[&str]) {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name));
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("golden")
+        .join(name);
+    let _ = fs::remove_dir_all(&out_dir);
+
+    let rendered = render(chapter, args, &out_dir);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::copy(&rendered, &golden).unwrap();
+        return;
+    }
+
+    let actual = load_png(&rendered);
+    let expected = load_png(&golden);
+    assert_eq!(
+        (actual.width, actual.height),
+        (expected.width, expected.height),
+        "{} rendered at a different size than {:?}",
+        chapter,
+        golden
+    );
+
+    let (mismatched, diff_image) = diff(&actual, &expected);
+    if mismatched > 0 {
+        let diff_path = out_dir.join("diff.png");
+        save_png(&diff_path, &diff_image);
+        panic!(
+            "{} differs from {:?} in {} pixels\n  rendered: {:?}\n  diff:     {:?}",
+            chapter, golden, mismatched, rendered, diff_path
+        );
+    }
+}
+
+macro_rules! golden_tests {
+    ($($name:ident => $chapter:literal $([$($arg:literal),*])?,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                check(stringify!($name), $chapter, &[$($($arg),*)?])
+            }
+        )*
+    };
+}
+
+golden_tests! {
+    ch_1 => "ch_1",
+    ch_2 => "ch_2",
+    ch_2_va => "ch_2_va",
+    ch_3 => "ch_3",
+    ch_3_2 => "ch_3_2",
+    ch_3_3 => "ch_3_3",
+    ch_3_4 => "ch_3_4",
+    ch_4_1 => "ch_4_1",
+    ch_4_2 => "ch_4_2",
+    ch_4_3 => "ch_4_3",
+    ch_4_4 => "ch_4_4",
+    ch_5_1 => "ch_5_1",
+    ch_5_2 => "ch_5_2",
+    ch_5_3 => "ch_5_3",
+    ch_5_4 => "ch_5_4",
+    ch_5_5 => "ch_5_5",
+    ch_6_1 => "ch_6_1",
+    ch_6_2 => "ch_6_2",
+    ch_6_3 => "ch_6_3",
+    ch_4_4_wide => "ch_4_4" ["--width", "640", "--height", "320"],
+    ch_6_1_tall => "ch_6_1" ["--width", "300", "--height", "500"],
+}